@@ -1,17 +1,120 @@
+use crate::template::{self, Field, Part};
+use crate::{bookmarks, fuzzy, import, outline, page, utils};
 use js_sys::Array;
 use log::*;
-use rand::{rngs::OsRng, seq::IteratorRandom};
+use qrcode::{Color, EcLevel, QrCode, Version};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 use serde_derive::{Deserialize, Serialize};
-use std::{collections::BTreeMap, time::Duration};
-use web_sys::{Blob, Url};
-use yew::format::{Json, Text};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{
+    Blob, BroadcastChannel, CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlElement,
+    HtmlImageElement, MessageEvent, StorageEvent, Url,
+};
+use yew::format::{Json, Nothing, Text};
 use yew::prelude::*;
 use yew::services::{
+    fetch::{FetchTask, Request, Response},
+    keyboard::{KeyListenerHandle, KeyboardService},
+    reader::{File, FileData, ReaderService, ReaderTask},
+    render::RenderTask,
     storage::{Area, StorageService},
-    DialogService, IntervalService, Task,
+    timeout::TimeoutTask,
+    DialogService, FetchService, IntervalService, RenderService, Task, TimeoutService,
 };
 
 const KEY: &str = "automatic-spoon.self";
+// Inlined into boards saved as web pages
+const STYLESHEET: &str = include_str!("../static/autospoon.css");
+// Index of named boards.  The unnamed default board lives at KEY and named
+// boards at "KEY.<name>".
+const BOARDS_KEY: &str = "automatic-spoon.boards";
+const CHANNEL: &str = "automatic-spoon";
+// Most search results to render at once
+const SEARCH_LIMIT: usize = 20;
+// Oldest trashed items are dropped past this many
+const TRASH_LIMIT: usize = 50;
+const HISTORY_LIMIT: usize = 100;
+const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+// Picks each group can take back
+const PICK_UNDO_LIMIT: usize = 20;
+// Picks shown in the strip above a group
+const RECENT_LIMIT: usize = 8;
+const IMPORT_PROBLEM_LIMIT: usize = 10;
+// Roll Until gives up after this many draws
+const ROLL_UNTIL_LIMIT: usize = 100;
+// Long enough for the busy overlay to be painted before an import starts
+const BUSY_DELAY_MS: u64 = 50;
+const TICK_MS: u64 = 100;
+// The slowest a flashing group changes candidates, at the end of a spin
+const SPIN_SLOWEST_MS: f64 = 800.0;
+// Embedded (data URL) images bigger than this are flagged before export
+const LARGE_IMAGE_BYTES: usize = 100_000;
+// Stands in for embedded images left out of an export
+const EXCLUDED_IMAGE: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' \
+    width='100' height='100'%3E%3Crect width='100' height='100' fill='lightgray'/%3E%3C/svg%3E";
+// Groups nested deeper than this are not shown or rolled, which also stops
+// a group that contains itself from recursing forever
+const MAX_GROUP_DEPTH: usize = 8;
+// How long a toast stays up, in milliseconds
+const TOAST_MS: f64 = 4000.0;
+
+struct Shortcut {
+    key: &'static str,
+    // Only with Alt held, and only without it otherwise
+    alt: bool,
+    description: &'static str,
+    // Whether the shortcut still fires while typing in a text field
+    while_typing: bool,
+    msg: fn() -> Msg,
+}
+
+// Global keyboard shortcuts.  The help overlay is rendered from this table.
+const SHORTCUTS: &[Shortcut] = &[
+    Shortcut {
+        key: "?",
+        alt: false,
+        description: "Show or hide this help",
+        while_typing: false,
+        msg: || Msg::ToggleHelp,
+    },
+    Shortcut {
+        key: "Escape",
+        alt: false,
+        description: "Close help, settings, the item editor or a fullscreen result",
+        while_typing: true,
+        msg: || Msg::CloseOverlay,
+    },
+    Shortcut {
+        key: "ArrowLeft",
+        alt: true,
+        description: "Previous group",
+        while_typing: false,
+        msg: || Msg::CycleGroup(-1),
+    },
+    Shortcut {
+        key: "ArrowRight",
+        alt: true,
+        description: "Next group",
+        while_typing: false,
+        msg: || Msg::CycleGroup(1),
+    },
+    Shortcut {
+        key: " ",
+        alt: false,
+        description: "Draw or unlock the pick under the mouse",
+        while_typing: false,
+        msg: || Msg::ToggleHoveredList,
+    },
+];
+// How long the group board takes to fade in after switching groups
+const GROUP_SWITCH_MS: f64 = 300.0;
 
 pub struct App {
     link: ComponentLink<Self>,
@@ -19,14 +122,599 @@ pub struct App {
     dialog: DialogService,
     _interval: IntervalService,
     _heartbeat: Box<dyn Task>,
+    render: RenderService,
+    _restore_scroll: Option<RenderTask>,
+    list_panel: NodeRef,
+    group_panel: NodeRef,
+    // Shared by every random selection.  Rendering draws from it too, so it
+    // has to be usable through &self.
+    rng: RefCell<StdRng>,
+    // Mirror tabs (`?mirror=1`) apply state posted to the channel by the
+    // editing tab instead of saving their own.  Without BroadcastChannel
+    // support every tab just runs independently.
+    mirror: bool,
+    channel: Option<BroadcastChannel>,
+    _on_mirror_message: Option<Closure<dyn FnMut(MessageEvent)>>,
+    // Other tabs saving the board.  Mirrors hear about that on the channel
+    // instead.
+    _on_storage: Option<Closure<dyn FnMut(StorageEvent)>>,
+    preview_frame: NodeRef,
+    _watch_preview: Option<RenderTask>,
+    _on_preview_load: Option<Closure<dyn FnMut()>>,
+    edit_modal: NodeRef,
+    _focus_edit_modal: Option<RenderTask>,
+    // Whatever had focus when the item editor opened, to hand it back on close
+    edit_modal_return: Option<HtmlElement>,
+    // `?embed=group:<name>` shows only that group's board, for an iframe on
+    // another site.  Like a mirror it never saves.
+    embed: Option<String>,
+    _keydown: Option<KeyListenerHandle>,
+    fetch: FetchService,
+    _fetch_link: Option<FetchTask>,
+    // The board named by `?import=<url>`, fetched once on startup
+    _fetch_board: Option<FetchTask>,
+    fullscreen_panel: NodeRef,
+    confetti_canvas: NodeRef,
+    qr_canvas: NodeRef,
+    // The print board, rendered out of sight for Export Web Page to copy
+    snapshot_panel: NodeRef,
+    _draw_qr: Option<RenderTask>,
+    reader: ReaderService,
+    _read_bookmarks: Option<ReaderTask>,
+    _enter_fullscreen: Option<RenderTask>,
+    // requestFullscreen() only asks; these say whether the browser agreed
+    _on_fullscreen: Option<Closure<dyn FnMut()>>,
+    _print: Option<RenderTask>,
+    timeout: TimeoutService,
+    _busy: Option<TimeoutTask>,
+    pending_import: Option<Import>,
+    // Loads the image URL being edited off-screen to see whether it works
+    image_check: Option<ImageCheck>,
+    boards: Boards,
     state: State,
     view: View,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Clone)]
 pub struct State {
     lists: BTreeMap<String, Vec<Item>>,
-    groups: BTreeMap<String, Vec<String>>,
+    groups: BTreeMap<String, Vec<GroupEntry>>,
+    // Optional short emoji/label shown before a list's name
+    #[serde(default)]
+    list_icons: BTreeMap<String, String>,
+    #[serde(default)]
+    list_settings: BTreeMap<String, ListSettings>,
+    // Lists may not grow past these sizes.  Lists already over their limit
+    // keep their items but can't gain more.
+    #[serde(default)]
+    list_max: BTreeMap<String, usize>,
+    // A CSS color or image URL shown behind a group's board
+    #[serde(default)]
+    group_backgrounds: BTreeMap<String, String>,
+    // The selections to restore on the next load
+    #[serde(default)]
+    current_list: Option<String>,
+    #[serde(default)]
+    current_group: Option<String>,
+    // Removed items and the list they came from, oldest first
+    #[serde(default)]
+    trashed: Vec<(String, Item)>,
+    // Fill in an empty name and image from a newly entered link's page.  Off
+    // by default since it contacts the linked site.
+    #[serde(default)]
+    fetch_link_details: bool,
+    // Sidebar order of lists.  Lists missing from it follow alphabetically.
+    #[serde(default)]
+    list_order: Vec<String>,
+    // Sidebar order of groups, which works the same way
+    #[serde(default)]
+    group_order: Vec<String>,
+    // Frozen picks, oldest first
+    #[serde(default)]
+    history: Vec<Draw>,
+    // Record each draw's seed and odds in the history so it can be verified
+    #[serde(default)]
+    fair_draws: bool,
+    // Freeze every group's lists as soon as the board loads, for a kiosk.
+    // `?autofreeze=1` does the same for one page load.
+    #[serde(default)]
+    autofreeze: bool,
+    // Forget draws older than this many days.  0 keeps them, up to
+    // HISTORY_LIMIT.
+    #[serde(default)]
+    history_ttl_days: u32,
+    // Edit the current item in a dialog rather than in the bottom pane
+    #[serde(default)]
+    item_editor_dialog: bool,
+    #[serde(default)]
+    group_settings: BTreeMap<String, GroupSettings>,
+    #[serde(default)]
+    spin: SpinSettings,
+    // When each list's items were last edited, in ms since the epoch.
+    // Drawing doesn't count unless it removes the item.
+    #[serde(default)]
+    list_modified: BTreeMap<String, f64>,
+}
+
+impl State {
+    // Put the board-wide preferences back to their defaults, leaving the
+    // lists, groups and everything drawn from them alone
+    pub fn reset_settings(&mut self) {
+        let defaults = State::default();
+        self.fetch_link_details = defaults.fetch_link_details;
+        self.fair_draws = defaults.fair_draws;
+        self.autofreeze = defaults.autofreeze;
+        self.history_ttl_days = defaults.history_ttl_days;
+        self.item_editor_dialog = defaults.item_editor_dialog;
+        self.spin = defaults.spin;
+    }
+    // Fix what old bugs or hand editing can leave behind that the UI doesn't
+    // cope with.  Returns a description of each fix.
+    pub fn repair(&mut self) -> Vec<String> {
+        let mut fixed = Vec::new();
+        if self.lists.remove("").is_some() {
+            fixed.push("removed a list with no name".to_owned());
+        }
+        if self.groups.remove("").is_some() {
+            fixed.push("removed a group with no name".to_owned());
+        }
+        let unnamed = self.list_icons.remove("").is_some()
+            | self.list_settings.remove("").is_some()
+            | self.list_max.remove("").is_some()
+            | self.group_backgrounds.remove("").is_some()
+            | self.group_settings.remove("").is_some();
+        if unnamed {
+            fixed.push("removed settings saved for no name".to_owned());
+        }
+        for order in [&mut self.list_order, &mut self.group_order] {
+            let before = order.len();
+            order.retain(|name| !name.is_empty());
+            if order.len() != before {
+                fixed.push("removed blank names from the sidebar order".to_owned());
+            }
+        }
+        let lists = &self.lists;
+        let names: BTreeSet<String> = self.groups.keys().cloned().collect();
+        for (group, entries) in self.groups.iter_mut() {
+            let before = entries.len();
+            entries.retain(|entry| match entry {
+                GroupEntry::List(name) => lists.contains_key(name),
+                GroupEntry::Group { group } => names.contains(group),
+            });
+            if entries.len() != before {
+                fixed.push(format!(
+                    "removed {} missing lists or groups from {}",
+                    before - entries.len(),
+                    group
+                ));
+            }
+        }
+        fixed.dedup();
+        fixed
+    }
+}
+
+// How the flashing candidates in a group slow down.  The candidate changes
+// every Tick at first and every SPIN_SLOWEST_MS by the end of the spin.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct SpinSettings {
+    // 0 keeps spinning until the list is clicked
+    #[serde(default)]
+    duration_ms: f64,
+    #[serde(default)]
+    easing: Easing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Easing {
+    Linear,
+    #[default]
+    EaseOut,
+    Bounce,
+}
+
+impl Easing {
+    // How far the spin has slowed, from 0 to 1, after `progress` of it
+    fn apply(self, progress: f64) -> f64 {
+        let p = progress.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => p,
+            Easing::EaseOut => 1.0 - (1.0 - p) * (1.0 - p),
+            Easing::Bounce => {
+                // The usual bounce-out curve, from Robert Penner's easings
+                let (n, d) = (7.5625, 2.75);
+                if p < 1.0 / d {
+                    n * p * p
+                } else if p < 2.0 / d {
+                    let p = p - 1.5 / d;
+                    n * p * p + 0.75
+                } else if p < 2.5 / d {
+                    let p = p - 2.25 / d;
+                    n * p * p + 0.9375
+                } else {
+                    let p = p - 2.625 / d;
+                    n * p * p + 0.984_375
+                }
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct GroupSettings {
+    #[serde(default)]
+    reveal_order: RevealOrder,
+    // Burst confetti over each pick as it is drawn or revealed
+    #[serde(default)]
+    celebrate: bool,
+    // Length of the countdown before a timed draw
+    #[serde(default)]
+    countdown_seconds: Option<u32>,
+    // Weights used instead of the items' own when drawing in this group,
+    // by list and then item name.  Items sharing a name share an override,
+    // and renaming an item takes its override along.
+    #[serde(default)]
+    weights: BTreeMap<String, BTreeMap<String, f64>>,
+    // Clicking a pick with a link opens it in a pane here instead of a tab
+    #[serde(default)]
+    preview_links: bool,
+    // Freezing one of its lists unfreezes the rest, so one pick shows at a
+    // time, e.g. for revealing quiz answers
+    #[serde(default)]
+    exclusive_reveal: bool,
+    // Most the values of the group's picks should add up to
+    #[serde(default)]
+    value_cap: Option<f64>,
+}
+
+// A link open in the preview pane
+pub struct LinkPreview {
+    url: String,
+    loaded: bool,
+}
+
+// The order Reveal Next turns over a group's hidden picks
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealOrder {
+    #[default]
+    AsListed,
+    Reversed,
+    Shuffled,
+}
+
+// A named weight an item can take instead of a number of its own
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tier {
+    name: String,
+    weight: f64,
+}
+
+// Used by every list until its tier weights are edited
+const DEFAULT_TIERS: [(&str, f64); 5] = [
+    ("Common", 60.0),
+    ("Uncommon", 25.0),
+    ("Rare", 10.0),
+    ("Epic", 4.0),
+    ("Legendary", 1.0),
+];
+
+// A field every item in a catalog-style list is expected to have
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Required {
+    Image,
+    Link,
+}
+
+impl Required {
+    pub fn missing(self, item: &Item) -> bool {
+        let field = match self {
+            Required::Image => &item.image,
+            Required::Link => &item.link,
+        };
+        field
+            .as_ref()
+            .map(|text| text.trim().is_empty())
+            .unwrap_or(true)
+    }
+    pub fn label(self) -> &'static str {
+        match self {
+            Required::Image => "image",
+            Required::Link => "link",
+        }
+    }
+}
+
+// How a list picks among its enabled items, when it isn't in rotation
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawMode {
+    // By each item's weight
+    #[default]
+    Weighted,
+    // Every item equally, whatever its weight
+    Uniform,
+    // Every item once, in random order, before any item comes up again
+    Bag,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Draw {
+    time: f64,
+    list: String,
+    item: Option<String>,
+    #[serde(default)]
+    proof: Option<DrawProof>,
+}
+
+// Everything needed to repeat a draw: seeding ChaCha20Rng with `seed` and
+// picking from `candidates` by weight gives `winner` again.  StdRng would be
+// free to change algorithm between rand releases and break every stored
+// proof; rand 0.7's happens to be ChaCha20 too, so older proofs still verify.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DrawProof {
+    seed: u64,
+    algorithm: String,
+    // Roll Until makes several draws from the one seed and keeps the last
+    #[serde(default = "default_attempts")]
+    attempts: usize,
+    candidates: Vec<(Option<String>, f64)>,
+    winner: usize,
+}
+
+// A member of a group.  Lists are stored as plain strings, as every entry
+// was before groups could be nested.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum GroupEntry {
+    List(String),
+    Group { group: String },
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ListSettings {
+    // Drawing an item removes it from the list
+    #[serde(default)]
+    consume_on_roll: bool,
+    // Always draw whichever item was chosen longest ago
+    #[serde(default)]
+    rotation: bool,
+    // Weights are entered as percentages of the enabled items.  They are
+    // still used as plain weights when drawing.
+    #[serde(default)]
+    percentages: bool,
+    #[serde(default)]
+    draw_mode: DrawMode,
+    // How many draws an item sits out after being drawn
+    #[serde(default)]
+    cooldown: usize,
+    // Empty for DEFAULT_TIERS
+    #[serde(default)]
+    tiers: Vec<Tier>,
+    // Weights are whole numbers of raffle tickets
+    #[serde(default)]
+    tickets: bool,
+    // How a drawn item is shown, with placeholders from template.rs.  None
+    // for the usual name, image and comment.
+    #[serde(default)]
+    template: Option<String>,
+    // Items without this field are flagged, but can still be edited and drawn
+    #[serde(default)]
+    required: Option<Required>,
+    // Ask before drawing while any item is missing the required field
+    #[serde(default)]
+    warn_missing: bool,
+    // Roll a number from this range instead of drawing an item.  The list's
+    // items are kept, but not drawn, while it is set.
+    #[serde(default)]
+    numeric: Option<NumericList>,
+    #[serde(default)]
+    on_empty: EmptyPolicy,
+    #[serde(default)]
+    draw_style: DrawStyle,
+    // Items a bag-mode list has already drawn this cycle.  Saved so a
+    // reload doesn't start the cycle over.
+    #[serde(default)]
+    bag: BTreeSet<usize>,
+}
+
+// What the groups do with a list once its last item is deleted
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyPolicy {
+    // Say the list is empty, with a link to fill it
+    #[default]
+    Placeholder,
+    // Take the list out of every group it is in
+    RemoveFromGroups,
+    // Leave it in place, showing nothing
+    Keep,
+}
+
+// How a list's pick looks while the group spins and as it freezes.  Every
+// style but Flash is a CSS animation, skipped when motion is reduced.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawStyle {
+    // Candidates change in place, like a slot machine
+    #[default]
+    Flash,
+    Fade,
+    Flip,
+    Scroll,
+}
+
+impl DrawStyle {
+    fn class(self) -> &'static str {
+        match self {
+            DrawStyle::Flash => "",
+            DrawStyle::Fade => "draw-fade",
+            DrawStyle::Flip => "draw-flip",
+            DrawStyle::Scroll => "draw-scroll",
+        }
+    }
+}
+
+// How long a freezing pick plays its draw style's reveal
+const REVEAL_MS: f64 = 600.0;
+
+// A list that is a range of numbers, e.g. 1 to 100 for a d100
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NumericList {
+    min: i64,
+    max: i64,
+    step: i64,
+}
+
+impl Default for NumericList {
+    fn default() -> Self {
+        NumericList {
+            min: 1,
+            max: 6,
+            step: 1,
+        }
+    }
+}
+
+impl NumericList {
+    // How many numbers the range can roll
+    fn count(&self) -> u64 {
+        if self.step <= 0 || self.max < self.min {
+            return 0;
+        }
+        let span = (self.max as i128 - self.min as i128) / self.step as i128;
+        (span as u64).saturating_add(1)
+    }
+    // An item named after a random number from the range
+    fn roll(&self, rng: &mut impl Rng) -> Option<Item> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let n = self.min as i128 + rng.gen_range(0, count) as i128 * self.step as i128;
+        Some(Item {
+            name: Some(n.to_string()),
+            // A roll counts for what it shows, so dice can be summed
+            value: Some(n as f64),
+            ..Item::default()
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum NumericBound {
+    Min,
+    Max,
+    Step,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Boards {
+    names: BTreeSet<String>,
+    // Empty for the default board
+    current: String,
+}
+
+const QR_MODULE_PX: u32 = 4;
+const QR_QUIET_ZONE: usize = 4;
+// Past this the modules get too small for a phone camera to read off a screen
+const QR_MAX_VERSION: i16 = 25;
+// The bytes a version 25 code holds at level L
+const QR_CAPACITY: usize = 1273;
+// A share link's board can't inflate past this, however small the link
+const SHARE_LINK_MAX_BYTES: usize = 5_000_000;
+
+// The seeded draws' RNG, as named in their proofs
+const DRAW_RNG: &str = "ChaCha20";
+
+const CELEBRATE_MS: f64 = 1500.0;
+const CONFETTI_PIECES: usize = 60;
+// Downward pull on the confetti, in CSS pixels per second squared
+const CONFETTI_GRAVITY: f64 = 600.0;
+
+// Confetti over a list's freshly drawn card
+pub struct Celebration {
+    list: String,
+    started: f64,
+    pieces: Vec<Confetti>,
+}
+
+// One piece, thrown from the middle of the card.  Speeds are in CSS pixels
+// per second.
+struct Confetti {
+    dx: f64,
+    dy: f64,
+    hue: f64,
+}
+
+// A timed draw: counts down, then draws every list in the group that is
+// still flashing
+pub struct Countdown {
+    group: String,
+    // Time left when it was last started or paused
+    remaining_ms: f64,
+    // When it was last started or resumed, or None while paused
+    resumed: Option<f64>,
+}
+
+impl Countdown {
+    pub fn left(&self, now: f64) -> f64 {
+        self.remaining_ms - self.resumed.map(|resumed| now - resumed).unwrap_or(0.0)
+    }
+}
+
+// What Roll Group is about to do, shown before it does it
+pub struct RollPreview {
+    group: String,
+    roll: Vec<String>,
+    // Each list or group left out, and why
+    skip: Vec<(String, &'static str)>,
+}
+
+// A list as it was before a pick from the group board, for Undo Pick
+pub struct PickUndo {
+    list: String,
+    // The pick's time, which finds its entry in the history
+    time: f64,
+    items: Vec<Item>,
+    // The list straight after the pick, as JSON.  If it differs now the
+    // list has been edited since, even by a rename or a Sort that kept its
+    // length, and putting `items` back would lose the edit.
+    after: String,
+    // What the board showed before: the earlier pick, or None while the
+    // list was still flashing
+    previous: Option<Chosen>,
+    revealed: bool,
+    last_consumed: Option<(usize, Item)>,
+    bag: BTreeSet<usize>,
+    // Picks a one-at-a-time group put away to show this one
+    thawed: Vec<(String, Chosen)>,
+}
+
+// Which part of the board a narrow screen shows, in swipe order
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum MobileTab {
+    #[default]
+    Groups,
+    Lists,
+    Item,
+}
+
+const MOBILE_TABS: [(MobileTab, &str); 3] = [
+    (MobileTab::Groups, "Groups"),
+    (MobileTab::Lists, "Lists"),
+    (MobileTab::Item, "Item"),
+];
+
+// How far a finger has to travel sideways, in CSS pixels, to change tabs
+const SWIPE_DISTANCE: i32 = 60;
+
+// Where a touch started and where it is now, in client coordinates
+pub struct Swipe {
+    start: (i32, i32),
+    last: (i32, i32),
 }
 
 #[derive(Default)]
@@ -35,10 +723,125 @@ pub struct View {
     new_list_name: String,
     current_group: String,
     new_group_name: String,
-    cache: BTreeMap<String, Item>,
+    cache: BTreeMap<String, Chosen>,
+    // The item each flashing list in the current group is showing
+    flash: BTreeMap<String, Chosen>,
     current_item: Option<usize>,
+    multi_select: bool,
+    // Indices into the current list
+    selected_items: BTreeSet<usize>,
     export_url: Option<String>,
     cached_export: String,
+    // Board saved by another tab, waiting for the user to stop typing,
+    // with how many saves this tab had made when it arrived
+    external_state: Option<(String, u64)>,
+    // Saves this tab has made, to tell whether it saved over a held board
+    saves: u64,
+    // Each list as last saved, to tell which ones an update changed.  None
+    // after the whole board is replaced, so loading isn't taken for edits.
+    list_snapshots: Option<BTreeMap<String, String>>,
+    // Sidebar shows the most recently edited lists first
+    lists_by_edited: bool,
+    // Panel scroll offsets, keyed by "list:<name>" or "group:<name>"
+    scroll: BTreeMap<String, i32>,
+    outline: String,
+    // Folders from an uploaded bookmarks file, each with the list it is going
+    // into.  An empty list name leaves that folder out.
+    bookmark_import: Option<Vec<(bookmarks::Folder, String)>>,
+    json_import: String,
+    bulk_images: String,
+    // What the Roll Until controls are set to look for
+    roll_until: String,
+    roll_until_by_weight: bool,
+    // Each list's most recent item removed by a consuming draw, and the
+    // index it came from
+    last_consumed: BTreeMap<String, (usize, Item)>,
+    // Frozen picks stay face down until revealed
+    surprise: bool,
+    // When the current spin started and when its candidates last changed,
+    // in ms since the epoch.  A spin starts on the first Tick after this is 0.
+    spin_start: f64,
+    last_flash: f64,
+    // Hidden picks still to be turned over by Reveal Next, in order
+    reveal_queue: Vec<String>,
+    // (group, list) pairs whose face-down pick has been revealed
+    revealed: BTreeSet<(String, String)>,
+    // (group, list) pairs whose pick is kept through Unlock All Lists, and
+    // can't be rerolled or unlocked until unpinned
+    pinned_picks: BTreeSet<(String, String)>,
+    show_help: bool,
+    show_settings: bool,
+    search: String,
+    new_board_name: String,
+    // Message and the time (ms since epoch) it disappears
+    toast: Option<(String, f64)>,
+    // Find and Replace in the current list
+    replace_field: ItemField,
+    replace_find: String,
+    replace_with: String,
+    replace_ignore_case: bool,
+    // Nothing was saved when the page loaded, so show the sample board offer
+    offer_sample: bool,
+    // A group's picks as text to paste elsewhere, shown over the page
+    group_picks: Option<String>,
+    // Groups whose picks can't be drawn, rerolled or unlocked for now
+    group_locked: BTreeSet<String>,
+    // When the keyboard last switched groups, in ms since the epoch
+    group_switched: f64,
+    // When each list's pick was last drawn, while its reveal is playing
+    revealing: BTreeMap<String, f64>,
+    // Page height last posted to the page embedding this one
+    embed_height: i32,
+    // What the current toast's Undo button puts back.  Once the toast is
+    // gone the deletion is final.
+    undo_delete: Option<Deleted>,
+    dragging_list: Option<String>,
+    dragging_group: Option<String>,
+    // List whose frozen pick is shown on its own, and whether the browser
+    // granted real fullscreen for it
+    fullscreen: Option<String>,
+    fullscreen_active: bool,
+    // Replace large embedded images in the export file with a placeholder
+    export_without_images: bool,
+    // Showing the plain board that gets printed
+    printing: bool,
+    // The same board, hidden, while Export Web Page copies it
+    exporting_html: bool,
+    // An import is running and the page is covered by a spinner
+    busy: bool,
+    // Normalize Weights scales a list's enabled items to add up to 1
+    // rather than 100.  Percentage lists always use 100.
+    normalize_to_one: bool,
+    // Show the current list most likely first.  Pinned items stay on top.
+    sort_by_odds: bool,
+    // Dim the other panels and give the current list the room
+    focus_list: bool,
+    // Only show the current list's items with this tag
+    tag_filter: Option<String>,
+    // A list's items in a weighted random order, best first, and how many of
+    // them lead the order because they could be drawn
+    ranking: Option<(String, Vec<Item>, usize)>,
+    // Items each list skips for its next draw only
+    temp_excluded: BTreeMap<String, BTreeSet<usize>>,
+    // Only means anything when the screen is narrow enough for tabs
+    mobile_tab: MobileTab,
+    // A one-finger touch that might turn into a swipe between tabs
+    swipe: Option<Swipe>,
+    celebration: Option<Celebration>,
+    // The group board's list under the mouse, for the space bar shortcut
+    hovered_list: Option<String>,
+    // Each group's picks, most recent last
+    pick_undo: BTreeMap<String, Vec<PickUndo>>,
+    countdown: Option<Countdown>,
+    roll_preview: Option<RollPreview>,
+    // The board's share link as a QR code, to scan with another device
+    qr: Option<QrCode>,
+    link_preview: Option<LinkPreview>,
+    // The item editor is showing as a dialog rather than inline
+    edit_modal_open: bool,
+    // Advisory hints under the item edit form's fields, keyed by field.
+    // They never stop an edit from being saved.
+    item_problems: BTreeMap<&'static str, String>,
 }
 
 impl View {
@@ -49,22 +852,196 @@ impl View {
             ..Default::default()
         }
     }
+    pub fn for_state(state: &State) -> Self {
+        Self::new(
+            restore_selection(&state.current_list, &state.lists),
+            restore_selection(&state.current_group, &state.groups),
+        )
+    }
 }
 
-#[derive(Serialize, Deserialize, Default, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Item {
     name: Option<String>,
     image: Option<String>,
     link: Option<String>,
     comment: Option<String>,
-    // weight: f64?,
+    // Relative odds of being chosen, 1 when unset
+    #[serde(default)]
+    weight: Option<f64>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+    // When this item was last drawn, in ms since the epoch
+    #[serde(default)]
+    last_chosen: Option<f64>,
+    // Days this item may be drawn, bit 0 for Sunday through bit 6 for
+    // Saturday.  None means every day.
+    #[serde(default)]
+    weekdays: Option<u8>,
+    #[serde(default)]
+    tags: Vec<String>,
+    // Shown at the top of its list.  Only affects the display.
+    #[serde(default)]
+    pinned: bool,
+    // Draws from its list left before it can come up again
+    #[serde(default)]
+    cooldown: usize,
+    // Name of one of its list's tiers, whose weight it takes instead of
+    // `weight`
+    #[serde(default)]
+    tier: Option<String>,
+    // Show the name rather than the image while spinning, e.g. for spoiler
+    // art.  The image still shows once drawn.
+    #[serde(default)]
+    hide_in_flash: bool,
+    // Added to the item's class wherever it is shown, for the user's own
+    // stylesheet
+    #[serde(default)]
+    css_class: Option<String>,
+    // Sort and Shuffle leave it at its index, e.g. "None of the above" at
+    // the bottom
+    #[serde(default)]
+    position_locked: bool,
+    // Other names it goes by.  Search finds it by these, but they are never
+    // shown on the item itself.
+    #[serde(default)]
+    aliases: Vec<String>,
+    // Points, cost or the like, summed over a group's picks
+    #[serde(default)]
+    value: Option<f64>,
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const ALL_WEEKDAYS: u8 = 0b111_1111;
+
+fn default_attempts() -> usize {
+    1
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for Item {
+    fn default() -> Self {
+        Self {
+            name: None,
+            image: None,
+            link: None,
+            comment: None,
+            weight: None,
+            enabled: true,
+            last_chosen: None,
+            weekdays: None,
+            tags: Vec::new(),
+            pinned: false,
+            cooldown: 0,
+            tier: None,
+            hide_in_flash: false,
+            css_class: None,
+            position_locked: false,
+            aliases: Vec::new(),
+            value: None,
+        }
+    }
 }
 
 impl Item {
-    pub fn render_chosen(&self) -> Html {
+    // Same contents, ignoring when each was last drawn
+    pub fn duplicates(&self, other: &Item) -> bool {
+        self.name == other.name
+            && self.image == other.image
+            && self.link == other.link
+            && self.comment == other.comment
+            && self.weight == other.weight
+            && self.enabled == other.enabled
+            && self.weekdays == other.weekdays
+            && self.tags == other.tags
+            && self.aliases == other.aliases
+            && self.value == other.value
+            && self.tier == other.tier
+            && self.hide_in_flash == other.hide_in_flash
+            && self.css_class == other.css_class
+            && self.position_locked == other.position_locked
+    }
+    // `base` followed by the item's own class, if it has one
+    pub fn classes(&self, base: &str) -> String {
+        match self.css_class.as_deref().map(sanitize_class) {
+            Some(own) if !own.is_empty() && !base.is_empty() => format!("{} {}", base, own),
+            Some(own) if !own.is_empty() => own,
+            _ => base.to_owned(),
+        }
+    }
+    // Never below 0, whatever an import left in `weight`
+    pub fn base_weight(&self) -> f64 {
+        self.weight.unwrap_or(1.0).max(0.0)
+    }
+    // `day` counts from 0 for Sunday, as Date.getDay() does
+    pub fn available_on(&self, day: u32) -> bool {
+        self.weekdays
+            .map(|mask| mask & (1 << day) != 0)
+            .unwrap_or(true)
+    }
+    pub fn field(&self, field: ItemField) -> Option<&String> {
+        match field {
+            ItemField::Name => self.name.as_ref(),
+            ItemField::Link => self.link.as_ref(),
+            ItemField::Comment => self.comment.as_ref(),
+        }
+    }
+    pub fn field_mut(&mut self, field: ItemField) -> &mut Option<String> {
+        match field {
+            ItemField::Name => &mut self.name,
+            ItemField::Link => &mut self.link,
+            ItemField::Comment => &mut self.comment,
+        }
+    }
+    // A list's display template filled in with this item's fields.  Missing
+    // fields come out empty.
+    fn render_template(&self, template: &str) -> Html {
+        let a = |url: &String, inner: Html| {
+            html! {
+                <a href=url.as_str() rel="noreferrer noopener" target="_blank">{inner}</a>
+            }
+        };
+        let linked = |inner: Html| match self.link.as_ref() {
+            Some(url) => a(url, inner),
+            None => inner,
+        };
+        let part = |part: Part| match part {
+            Part::Text(text) => html! {{text}},
+            Part::Field(Field::Name) => match self.name.as_ref().or(self.link.as_ref()) {
+                Some(name) => html! {<span class="name">{linked(html! {{name}})}</span>},
+                None => html! {},
+            },
+            Part::Field(Field::Image) => match self.image.as_ref() {
+                Some(image) => {
+                    html! {<span class="image">{linked(html! {<img src=image/>})}</span>}
+                }
+                None => html! {},
+            },
+            Part::Field(Field::Link) => match self.link.as_ref() {
+                Some(url) => html! {<span class="link">{a(url, html! {{url}})}</span>},
+                None => html! {},
+            },
+            Part::Field(Field::Comment) => match self.comment.as_ref() {
+                Some(comment) => html! {<span class="comment">{comment}</span>},
+                None => html! {},
+            },
+        };
+        html! {
+            <div class=self.classes("item templated")>
+                {for template::parse(template).into_iter().map(part)}
+            </div>
+        }
+    }
+    pub fn render_chosen(&self, template: Option<&str>) -> Html {
+        if let Some(template) = template {
+            return self.render_template(template);
+        }
         if let Some(url) = self.link.as_ref() {
             html! {
-                <div class="item">
+                <div class=self.classes("item")>
                     <div class="name">
                         <a href=url.as_str() rel="noreferrer noopener" target="_blank">{self.name.as_ref().unwrap_or(url)}</a>
                     </div>
@@ -80,7 +1057,7 @@ impl Item {
             }
         } else {
             html! {
-                <div class={"item"}>
+                <div class=self.classes("item")>
                     {self.name.as_ref().map(|name| html!{<div class="name">{name}</div>}).unwrap_or_default()}
                     {self.image.as_ref().map(|url| html!{<div class="image"><img src=url/></div>}).unwrap_or_default()}
                     {self.comment.as_ref().map(|comment| html!{<div class="comment">{comment}</div>}).unwrap_or_default()}
@@ -88,7 +1065,95 @@ impl Item {
             }
         }
     }
-    pub fn render_edit(&self, link: &ComponentLink<App>) -> Html {
+    pub fn render_edit(
+        &self,
+        link: &ComponentLink<App>,
+        percentages: bool,
+        problems: &BTreeMap<&'static str, String>,
+        tiers: &[Tier],
+    ) -> Html {
+        let tier = self.tier.clone().unwrap_or_default();
+        let tier_select = html! {
+            <select id="item-tier" onchange=link.callback(|e: ChangeData| match e {
+                ChangeData::Select(select) => Msg::SetItemTier(match select.value() {
+                    name if name.is_empty() => None,
+                    name => Some(name),
+                }),
+                _ => Msg::Nothing,
+            })>
+                <option value="" selected=tier.is_empty()>{"Own weight"}</option>
+                {for tiers.iter().map(|t| html! {
+                    <option value=&t.name selected=t.name == tier>{&t.name}</option>
+                })}
+            </select>
+        };
+        let hint = |field: &str| match problems.get(field) {
+            Some(problem) => html! { <p class="warning">{problem}</p> },
+            None => html! {},
+        };
+        let weight = html! {
+            <li>
+                {tier_select}
+                <input id="item-weight" class="edit" type="text" inputmode="decimal"
+                    disabled=self.tier.is_some()
+                    placeholder=if percentages { "Percent" } else { "Weight" }
+                    value=self.weight.map(|weight| weight.to_string()).unwrap_or_default()
+                    oninput=link.callback(move |e: InputData| Msg::EditItemWeight(e.value))
+                />
+                <label>
+                    <input id="item-enabled" type="checkbox" checked=self.enabled
+                        onclick=link.callback(|_| Msg::ToggleItemEnabled)
+                    />
+                    {"Enabled"}
+                </label>
+                {hint("weight")}
+            </li>
+        };
+        let value = html! {
+            <li>
+                <input id="item-value" class="edit" type="text" inputmode="decimal"
+                    placeholder="Value (points, cost)"
+                    title="Added up over a group's picks"
+                    value=self.value.map(|value| value.to_string()).unwrap_or_default()
+                    oninput=link.callback(|e: InputData| Msg::EditItemValue(e.value))
+                />
+                {hint("value")}
+            </li>
+        };
+        let image = html! {
+            <li>
+                <input id="item-image" class="edit" type="text" placeholder="Image URL"
+                    value=&self.image.as_ref().cloned().unwrap_or_default()
+                    oninput=link.callback(move |e: InputData| Msg::EditItemImage(e.value))
+                />
+                <label>
+                    <input id="item-hide-in-flash" type="checkbox" checked=self.hide_in_flash
+                        onclick=link.callback(|_| Msg::ToggleHideInFlash)
+                    />
+                    {"Hide while spinning"}
+                </label>
+                {hint("image")}
+            </li>
+        };
+        let css_class = html! {
+            <li>
+                <input id="item-css-class" class="edit" type="text" placeholder="CSS class"
+                    title="Added to this item's class for your own stylesheet"
+                    value=self.css_class.clone().unwrap_or_default()
+                    onchange=link.callback(|e: ChangeData| match e {
+                        ChangeData::Value(text) => Msg::EditItemClass(text),
+                        _ => Msg::Nothing,
+                    })
+                />
+            </li>
+        };
+        let clear = html! {
+            <li>
+                <button class="clear" onclick=link.callback(|_| Msg::ClearItem)>
+                    {"Clear Item"}
+                </button>
+            </li>
+        };
         html! {
             <div class="item">
             <ul>
@@ -99,85 +1164,510 @@ impl Item {
                 />
                 {self.image.as_ref().map(|url| html!{<div class="image"><img src=url/></div>}).unwrap_or_default()}
             </li>
-            <li>
-                <input id="item-image" class="edit" type="text" placeholder="Image URL"
-                    value=&self.image.as_ref().cloned().unwrap_or_default()
-                    oninput=link.callback(move |e: InputData| Msg::EditItemImage(e.value))
-                />
-            </li>
+            {image}
             <li>
                 <input id="item-link" class="edit" type="text" placeholder="Link"
                     value=&self.link.as_ref().cloned().unwrap_or_default()
                     oninput=link.callback(move |e: InputData| Msg::EditItemLink(e.value))
+                    onchange=link.callback(|_| Msg::LinkSaved)
+                />
+                {hint("link")}
+            </li>
+            {weight}
+            {value}
+            {self.render_weekdays(link)}
+            <li>
+                <input id="item-tags" class="edit" type="text" placeholder="Tags, separated by commas"
+                    value=self.tags.join(", ")
+                    onchange=link.callback(|e: ChangeData| match e {
+                        ChangeData::Value(text) => Msg::EditItemTags(text),
+                        _ => Msg::Nothing,
+                    })
+                />
+            </li>
+            <li>
+                <input id="item-aliases" class="edit" type="text" placeholder="Also known as, separated by commas"
+                    title="Search finds the item by these too"
+                    value=self.aliases.join(", ")
+                    onchange=link.callback(|e: ChangeData| match e {
+                        ChangeData::Value(text) => Msg::EditItemAliases(text),
+                        _ => Msg::Nothing,
+                    })
                 />
             </li>
+            {css_class}
             <li>
                 <textarea id="item-comment" class="edit" placeholder="Comment"
+                    value=self.comment.as_ref().cloned().unwrap_or_default()
                     oninput=link.callback(move |e: InputData| Msg::EditItemComment(e.value))
-                >
-                {&self.comment.as_ref().cloned().unwrap_or_default()}
-                </textarea>
+                />
             </li>
+            {clear}
             </ul>
             </div>
         }
     }
+    fn render_weekdays(&self, link: &ComponentLink<App>) -> Html {
+        html! {
+            <li class="weekdays">
+                {for (0..7).zip(WEEKDAYS.iter()).map(|(day, name)| html! {
+                    <label>
+                        <input type="checkbox" checked=self.available_on(day)
+                            onclick=link.callback(move |_| Msg::ToggleItemWeekday(day))
+                        />
+                        {name}
+                    </label>
+                })}
+            </li>
+        }
+    }
     pub fn render_flash(&self) -> Html {
+        let class = self.classes("");
         match (
-            self.image.as_ref(),
+            self.image.as_ref().filter(|_| !self.hide_in_flash),
             self.name.as_ref(),
             self.comment.as_ref(),
             self.link.as_ref(),
         ) {
             (Some(url), _, _, _) => html! {
-                <img src=url/>
+                <img class=class src=url/>
             },
             (None, Some(name), _, _) => html! {
-                <p>{name}</p>
+                <p class=class>{name}</p>
             },
             (None, None, Some(comment), _) => html! {
-                <p>{comment}</p>
+                <p class=class>{comment}</p>
             },
             (None, None, None, Some(link)) => html! {
                 // This is the flash content, so don't make an actual link
-                <p>{link}</p>
+                <p class=class>{link}</p>
             },
             _ => html! {
-                <p>{"???"}</p>
+                <p class=class>{"???"}</p>
             },
         }
     }
 }
 
+// A frozen pick, and where it still sits in its list unless it was consumed
+#[derive(Clone)]
+pub struct Chosen {
+    item: Item,
+    idx: Option<usize>,
+}
+
+// What Roll Until keeps rolling for
+#[derive(Clone)]
+pub enum RollPredicate {
+    HasTag(String),
+    MinWeight(f64),
+}
+
+impl RollPredicate {
+    // `weight` is the one the draw gave the item, tier and all
+    fn matches(&self, item: &Item, weight: f64) -> bool {
+        match self {
+            RollPredicate::HasTag(tag) => item.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)),
+            RollPredicate::MinWeight(min) => weight >= *min,
+        }
+    }
+    fn describe(&self) -> String {
+        match self {
+            RollPredicate::HasTag(tag) => format!("tagged {}", tag),
+            RollPredicate::MinWeight(weight) => format!("with weight at least {}", weight),
+        }
+    }
+}
+
+// The text fields Find and Replace can work on
+#[derive(Clone, Copy, PartialEq, Default)]
+pub enum ItemField {
+    #[default]
+    Name,
+    Link,
+    Comment,
+}
+
+// Something just deleted, with enough of where it was to put it back
+enum Deleted {
+    // Ascending by index, as they were in the list
+    Items {
+        list: String,
+        items: Vec<(usize, Item)>,
+        // Groups the emptied list was taken out of
+        memberships: Vec<(String, usize)>,
+    },
+    List {
+        name: String,
+        items: Vec<Item>,
+        icon: Option<String>,
+        settings: Option<ListSettings>,
+        max: Option<usize>,
+        order: Option<usize>,
+        memberships: Vec<(String, usize)>,
+    },
+    Group {
+        name: String,
+        entries: Vec<GroupEntry>,
+        background: Option<String>,
+        settings: Option<GroupSettings>,
+        order: Option<usize>,
+        memberships: Vec<(String, usize)>,
+    },
+    GroupEntry {
+        group: String,
+        entry: GroupEntry,
+        positions: Vec<usize>,
+    },
+}
+
+// Work deferred until the busy overlay is showing
+enum Import {
+    Outline(String),
+    Json(String),
+}
+
+struct ImageCheck {
+    image: HtmlImageElement,
+    _on_load: Closure<dyn FnMut()>,
+    _on_error: Closure<dyn FnMut()>,
+}
+
+impl Drop for ImageCheck {
+    // A check that's been superseded mustn't call back into dropped closures
+    fn drop(&mut self) {
+        self.image.set_onload(None);
+        self.image.set_onerror(None);
+    }
+}
+
 pub enum Msg {
     CreateItem,
+    UpdateBulkImages(String),
+    SetReplaceField(ItemField),
+    UpdateReplaceFind(String),
+    UpdateReplaceWith(String),
+    ToggleReplaceIgnoreCase,
+    ReplaceInList {
+        field: ItemField,
+        find: String,
+        replace: String,
+    },
+    BulkAddImages(String),
     EditItemName(String),
     EditItemImage(String),
     EditItemLink(String),
+    ImageChecked {
+        url: String,
+        loaded: bool,
+    },
+    LinkSaved,
+    BoardFetched {
+        url: String,
+        board: Result<String, String>,
+    },
+    // The `?board=` a share link opened the page with
+    OpenShareLink(String),
+    LinkFetched {
+        list: String,
+        idx: usize,
+        link: String,
+        page: Option<String>,
+    },
+    ToggleFetchLinkDetails,
+    ToggleItemEditorDialog,
+    ToggleFairDraws,
+    ToggleAutofreeze,
+    EditHistoryTtl(String),
+    VerifyDraw(usize),
     EditItemComment(String),
+    EditItemClass(String),
+    EditItemTags(String),
+    EditItemAliases(String),
+    EditItemWeight(String),
+    EditItemValue(String),
+    ToggleItemEnabled,
+    ToggleHideInFlash,
+    SetItemTier(Option<String>),
+    EditTierWeight {
+        list: String,
+        tier: usize,
+        text: String,
+    },
+    TogglePinned(usize),
+    TogglePositionLocked(usize),
+    // Rearrange the current list's items around any position-locked ones
+    SortList,
+    ShuffleList,
+    // Blank out the focused item but keep it, and keep it focused
+    ClearItem,
+    CopyItemToList {
+        idx: usize,
+        target: String,
+    },
+    ToggleItemWeekday(u32),
     FocusItem(usize),
     BlurItem,
     CreateList,
     FocusList(String),
     BlurList,
     UpdateListName(String),
+    EditListIcon(String),
+    ToggleConsumeOnRoll(String),
+    ToggleRotation(String),
+    NormalizeWeights(String),
+    SetNormalizeToOne(bool),
+    EditListCooldown(String),
+    EditListTemplate(String),
+    ResetCooldowns(String),
+    SetDrawMode {
+        list: String,
+        mode: DrawMode,
+    },
+    SetRequired {
+        list: String,
+        required: Option<Required>,
+    },
+    ToggleWarnMissing(String),
+    SetEmptyPolicy {
+        list: String,
+        policy: EmptyPolicy,
+    },
+    SetDrawStyle {
+        list: String,
+        style: DrawStyle,
+    },
+    TogglePercentages(String),
+    ToggleTickets(String),
+    // Switch a list between drawing items and rolling a number
+    ToggleNumeric(String),
+    EditNumeric {
+        list: String,
+        bound: NumericBound,
+        text: String,
+    },
+    StepTickets {
+        idx: usize,
+        delta: i32,
+    },
+    EditListMax(String),
     RemoveList(String),
+    DragList(String),
+    DropList(String),
+    DragGroup(String),
+    DropGroup(String),
     RemoveListItem(usize),
+    ToggleMultiSelect,
+    ToggleSortByOdds,
+    ToggleFocusList,
+    ToggleListsByEdited,
+    // Tags in the current list
+    RenameTag {
+        from: String,
+        to: String,
+    },
+    DeleteTag(String),
+    FilterByTag(Option<String>),
+    RankList(String),
+    CloseRanking,
+    ToggleSelectItem(usize),
+    SelectAllItems,
+    SelectNoItems,
+    InvertSelection,
+    RemoveSelectedItems,
+    RestoreTrashed(usize),
+    DeleteTrashed(usize),
     CreateGroup,
+    InstantiateTemplate(TemplateId),
     FocusGroup(String),
+    // Step through the groups in sidebar order, wrapping around
+    CycleGroup(isize),
     BlurGroup,
-    AddToGroup(String),
+    AddToGroup(GroupEntry),
     UpdateGroupName(String),
+    EditGroupBackground(String),
     RemoveGroup(String),
-    RemoveGroupItem(String),
+    RemoveGroupItem(GroupEntry),
+    RemoveDanglingRef(GroupEntry),
+    PruneDanglingRefs,
     ThawAllLists,
+    ToggleGroupLock,
+    PreviewGroupRoll(String),
+    // Draw the lists in the preview being shown
+    RollGroup,
+    // Roll the group's unpinned lists until their values fit under its cap
+    RollWithinCap,
+    EditCountdown(String),
+    EditValueCap(String),
+    // An empty weight goes back to the item's own
+    EditGroupWeight {
+        list: String,
+        item: String,
+        weight: String,
+    },
+    StartCountdown,
+    // Pauses a running countdown and resumes a paused one
+    PauseCountdown,
+    CancelCountdown,
+    // Freeze every list in the group and show its picks as text
+    ExportGroupPicks(String),
+    EditSpinDuration(String),
+    SetSpinEasing(Easing),
+    OpenGroupLinks,
     FreezeList(String),
+    RerollList(String),
+    RollUntil {
+        list: String,
+        predicate: RollPredicate,
+    },
+    UpdateRollUntil(String),
+    SetRollUntilByWeight(bool),
+    UndoDraw(String),
+    UndoPick,
+    UndoDelete,
     ThawList(String),
+    TogglePinnedPick(String),
+    ExcludeChosen(String),
+    ToggleTempExcluded {
+        list: String,
+        idx: usize,
+    },
+    ClearTempExcluded(String),
+    // Freeze a history entry's list on that pick again
+    ShowRecent(usize),
+    ToggleSurprise,
+    RevealList(String),
+    RevealAll,
+    RevealNext,
+    SetRevealOrder(RevealOrder),
+    ToggleCelebrate,
     Purge,
+    ResetView,
+    RepairData,
+    LoadSampleData,
+    DismissSample,
+    UpdateOutline(String),
+    ReadBookmarks(File),
+    BookmarksRead(FileData),
+    SetBookmarkList {
+        folder: usize,
+        list: String,
+    },
+    ImportBookmarks,
+    CancelBookmarks,
+    ImportOutline(String),
+    UpdateJsonImport(String),
+    ImportJson(String),
+    RunImport,
+    RestoreScroll,
+    MirrorState(String),
+    // Another tab saved `data` under the storage key `key`
+    StorageChanged {
+        key: String,
+        data: String,
+    },
+    KeyDown(KeyboardEvent),
+    HoverList(Option<String>),
+    ToggleHoveredList,
+    ShowTab(MobileTab),
+    SwipeStart(TouchEvent),
+    SwipeMove(TouchEvent),
+    SwipeEnd,
+    ToggleHelp,
+    ShowSettings,
+    ResetSettings,
+    ShowFullscreen(String),
+    Print,
+    ExportHtml,
+    ExportReport,
+    TogglePreviewLinks,
+    ToggleExclusiveReveal,
+    PreviewLink(String),
+    // Listen for the preview frame loading, once it is on the page
+    WatchPreview,
+    PreviewLoaded,
+    ClosePreview,
+    FocusEditModal,
+    CloseEditModal,
+    RestoreEditFocus,
+    ShowQr,
+    // Paint the QR code once its canvas is on the page
+    DrawQr,
+    ToggleExportImages,
+    PrintNow,
+    ExportHtmlNow,
+    EnterFullscreen,
+    // The browser entered or left fullscreen, or refused to enter it
+    FullscreenChanged(bool),
+    CloseOverlay,
+    SwitchBoard(String),
+    UpdateBoardName(String),
+    CreateBoard,
+    SearchItems(String),
+    OpenSearchResult(String, usize),
     Tick,
     Nothing,
 }
 
+impl Msg {
+    // Whether a read-only mirror may act on it: looking around, printing and
+    // exporting, but nothing that changes the board
+    fn reads_only(&self) -> bool {
+        use Msg::*;
+        matches!(
+            self,
+            FocusItem(_)
+                | BlurItem
+                | FocusList(_)
+                | BlurList
+                | ToggleSortByOdds
+                | ToggleFocusList
+                | ToggleListsByEdited
+                | FilterByTag(_)
+                | RankList(_)
+                | CloseRanking
+                | FocusGroup(_)
+                | CycleGroup(_)
+                | BlurGroup
+                | ShowRecent(_)
+                | ResetView
+                | RestoreScroll
+                | MirrorState(_)
+                | KeyDown(_)
+                | HoverList(_)
+                | ToggleHoveredList
+                | ShowTab(_)
+                | SwipeStart(_)
+                | SwipeMove(_)
+                | SwipeEnd
+                | ToggleHelp
+                | ShowFullscreen(_)
+                | Print
+                | ExportHtml
+                | ExportReport
+                | PreviewLink(_)
+                | WatchPreview
+                | PreviewLoaded
+                | ClosePreview
+                | FocusEditModal
+                | CloseEditModal
+                | RestoreEditFocus
+                | ShowQr
+                | DrawQr
+                | ToggleExportImages
+                | PrintNow
+                | ExportHtmlNow
+                | EnterFullscreen
+                | FullscreenChanged(_)
+                | CloseOverlay
+                | SearchItems(_)
+                | OpenSearchResult(..)
+                | Tick
+                | Nothing
+        )
+    }
+}
+
 impl Component for App {
     type Message = Msg;
     type Properties = ();
@@ -187,40 +1677,189 @@ impl Component for App {
         let dialog = DialogService::new();
         let mut _interval = IntervalService::new();
         let _heartbeat =
-            Box::new(_interval.spawn(Duration::from_millis(100), link.callback(|_| Msg::Tick)));
-        let state = {
-            if let Json(Ok(restored_entries)) = storage.restore(KEY) {
-                restored_entries
+            Box::new(_interval.spawn(Duration::from_millis(TICK_MS), link.callback(|_| Msg::Tick)));
+        let mut boards = {
+            if let Json(Ok(boards)) = storage.restore(BOARDS_KEY) {
+                boards
             } else {
-                State::default()
+                Boards::default()
+            }
+        };
+        if !boards.names.contains(&boards.current) {
+            boards.current.clear();
+        }
+        let state = load_state(&storage, &boards.current);
+        let mut view = View::for_state(&state);
+        let mirror = utils::query_param("mirror").as_deref() == Some("1");
+        let embed = utils::query_param("embed")
+            .and_then(|embed| embed.strip_prefix("group:").map(str::to_owned));
+        if let Some(group) = embed.as_ref() {
+            view.current_group = group.clone();
+        }
+        view.offer_sample =
+            !mirror && embed.is_none() && state.lists.is_empty() && state.groups.is_empty();
+        let channel = BroadcastChannel::new(CHANNEL).ok();
+        let _on_mirror_message = match channel.as_ref() {
+            Some(channel) if mirror => {
+                let callback = link.callback(Msg::MirrorState);
+                let closure = Closure::wrap(Box::new(move |e: MessageEvent| {
+                    if let Some(data) = e.data().as_string() {
+                        callback.emit(data);
+                    }
+                }) as Box<dyn FnMut(MessageEvent)>);
+                channel.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+                Some(closure)
+            }
+            _ => None,
+        };
+        let _on_storage = match web_sys::window() {
+            Some(window) if !mirror => {
+                let callback = link.callback(|(key, data)| Msg::StorageChanged { key, data });
+                let closure = Closure::wrap(Box::new(move |e: StorageEvent| {
+                    if let (Some(key), Some(data)) = (e.key(), e.new_value()) {
+                        callback.emit((key, data));
+                    }
+                }) as Box<dyn FnMut(StorageEvent)>);
+                let _ = window
+                    .add_event_listener_with_callback("storage", closure.as_ref().unchecked_ref());
+                Some(closure)
             }
+            _ => None,
+        };
+        let _keydown = web_sys::window()
+            .map(|window| KeyboardService::register_key_down(&window, link.callback(Msg::KeyDown)));
+        let _on_fullscreen = web_sys::window()
+            .and_then(|w| w.document())
+            .map(|document| {
+                let callback = link.callback(Msg::FullscreenChanged);
+                let target = document.clone();
+                let closure = Closure::wrap(Box::new(move || {
+                    callback.emit(target.fullscreen_element().is_some());
+                }) as Box<dyn FnMut()>);
+                for event in ["fullscreenchange", "fullscreenerror"] {
+                    let _ = document
+                        .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref());
+                }
+                closure
+            });
+        let mut fetch = FetchService::new();
+        let _fetch_board = match utils::query_param("import") {
+            Some(url) if !mirror => fetch_board(&mut fetch, &link, url),
+            _ => None,
         };
-        let current_list = state.lists.keys().cloned().next().unwrap_or_default();
-        let current_group = state.groups.keys().cloned().next().unwrap_or_default();
-        let view = View::new(current_list, current_group);
-        App {
+        if let Some(board) = utils::query_param("board").filter(|_| !mirror) {
+            // A reload shouldn't offer to replace the board all over again
+            utils::remove_query_param("board");
+            link.send_message(Msg::OpenShareLink(board));
+        }
+        let autofreeze = !mirror
+            && (state.autofreeze || utils::query_param("autofreeze").as_deref() == Some("1"));
+        let mut app = App {
             link,
             storage,
             dialog,
             _interval,
             _heartbeat,
+            render: RenderService::new(),
+            _restore_scroll: None,
+            list_panel: NodeRef::default(),
+            group_panel: NodeRef::default(),
+            rng: RefCell::new(StdRng::from_entropy()),
+            mirror,
+            channel,
+            _on_mirror_message,
+            _on_storage,
+            embed,
+            _keydown,
+            fetch,
+            _fetch_link: None,
+            _fetch_board,
+            fullscreen_panel: NodeRef::default(),
+            confetti_canvas: NodeRef::default(),
+            qr_canvas: NodeRef::default(),
+            snapshot_panel: NodeRef::default(),
+            _draw_qr: None,
+            preview_frame: NodeRef::default(),
+            _watch_preview: None,
+            _on_preview_load: None,
+            edit_modal: NodeRef::default(),
+            _focus_edit_modal: None,
+            edit_modal_return: None,
+            reader: ReaderService::new(),
+            _read_bookmarks: None,
+            _enter_fullscreen: None,
+            _on_fullscreen,
+            _print: None,
+            timeout: TimeoutService::new(),
+            _busy: None,
+            pending_import: None,
+            image_check: None,
+            boards,
             state,
             view,
+        };
+        app.expire_history();
+        if autofreeze {
+            app.freeze_all_groups();
         }
+        app
     }
 
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         use Msg::*;
+        // The editing tab owns the board; a mirror only shows what it posts
+        if self.mirror && !msg.reads_only() {
+            return false;
+        }
+        if let CreateList | CreateGroup | FocusList(_) | FocusGroup(_) | BlurList | BlurGroup = msg
+        {
+            self.remember_scroll();
+            self._restore_scroll = Some(
+                self.render
+                    .request_animation_frame(self.link.callback(|_| Msg::RestoreScroll)),
+            );
+        }
         match msg {
             CreateList => {
-                let _ = self
-                    .state
-                    .lists
-                    .entry(self.view.new_list_name.clone())
-                    .or_default();
+                let name = self.view.new_list_name.clone();
+                if !self.state.lists.contains_key(&name) && !self.state.list_order.is_empty() {
+                    self.state.list_order.push(name.clone());
+                }
+                let _ = self.state.lists.entry(name).or_default();
                 self.view.current_list = self.view.new_list_name.split_off(0);
+                self.view.selected_items.clear();
+            }
+            InstantiateTemplate(id) => {
+                let label = LIST_TEMPLATES
+                    .iter()
+                    .find(|(template, _)| *template == id)
+                    .map(|(_, label)| *label)
+                    .unwrap_or_default();
+                // The typed-in name if there is one, otherwise the
+                // template's, numbered past any list already using it
+                let base = match self.view.new_list_name.trim() {
+                    "" => label.to_owned(),
+                    name => name.to_owned(),
+                };
+                let mut name = base.clone();
+                let mut n = 1;
+                while self.state.lists.contains_key(&name) {
+                    n += 1;
+                    name = format!("{} {}", base, n);
+                }
+                if !self.state.list_order.is_empty() {
+                    self.state.list_order.push(name.clone());
+                }
+                self.state.lists.insert(name.clone(), template_items(id));
+                self.view.new_list_name.clear();
+                self.view.current_list = name;
+                self.view.selected_items.clear();
             }
             CreateGroup => {
+                let name = &self.view.new_group_name;
+                if !self.state.groups.contains_key(name) && !self.state.group_order.is_empty() {
+                    self.state.group_order.push(name.clone());
+                }
                 let _ = self
                     .state
                     .groups
@@ -230,18 +1869,47 @@ impl Component for App {
             }
             FocusList(name) => {
                 self.view.current_list = name;
+                self.view.selected_items.clear();
+                self.view.tag_filter = None;
             }
             FocusGroup(name) => {
                 self.view.current_group = name;
+                self.view.spin_start = 0.0;
             }
             BlurList => {
                 self.view.current_list = "".to_owned();
                 self.view.current_item = None;
+                self.view.selected_items.clear();
             }
             BlurGroup => {
                 self.view.current_group = "".to_owned();
             }
+            CycleGroup(step) => {
+                let order = ordered(&self.state.group_order, &self.state.groups);
+                if order.is_empty() {
+                    return false;
+                }
+                let len = order.len() as isize;
+                let next = match order
+                    .iter()
+                    .position(|name| **name == self.view.current_group)
+                {
+                    Some(idx) => (idx as isize + step).rem_euclid(len),
+                    None if step < 0 => len - 1,
+                    None => 0,
+                };
+                self.view.current_group = order[next as usize].clone();
+                self.view.spin_start = 0.0;
+                self.view.group_switched = js_sys::Date::now();
+            }
             CreateItem => {
+                if self.list_is_full(&self.view.current_list) {
+                    self.dialog.alert(&format!(
+                        "List {} already has its maximum of {} items.",
+                        self.view.current_list, self.state.list_max[&self.view.current_list]
+                    ));
+                    return true;
+                }
                 self.view.current_item =
                     self.state
                         .lists
@@ -250,29 +1918,330 @@ impl Component for App {
                             list.push(Item::default());
                             list.len() - 1
                         });
+                self.view.item_problems.clear();
+                self.image_check = None;
+                self.open_item_editor();
             }
-            FocusItem(idx) => {
-                self.view.current_item = Some(idx);
+            SetReplaceField(field) => {
+                self.view.replace_field = field;
             }
-            BlurItem => {
-                self.view.current_item = None;
+            UpdateReplaceFind(text) => {
+                self.view.replace_find = text;
             }
-            AddToGroup(entry) => {
-                self.state
-                    .groups
-                    .get_mut(&self.view.current_group)
-                    .map(|group| group.push(entry));
+            UpdateReplaceWith(text) => {
+                self.view.replace_with = text;
             }
-            UpdateListName(text) => {
-                self.view.new_list_name = text;
+            ToggleReplaceIgnoreCase => {
+                self.view.replace_ignore_case = !self.view.replace_ignore_case;
             }
-            EditItemName(text) => {
-                if let Some(item) = self.get_current_item_mut() {
+            ReplaceInList {
+                field,
+                find,
+                replace,
+            } => {
+                let changes = self.replacements(field, &find, &replace);
+                if changes.is_empty() {
+                    self.toast(format!("No items contain \"{}\"", find));
+                    return true;
+                }
+                if !self.dialog.confirm(&format!(
+                    "Replace \"{}\" with \"{}\" in {} items?",
+                    find,
+                    replace,
+                    changes.len()
+                )) {
+                    return false;
+                }
+                if let Some(list) = self.get_current_list_mut() {
+                    for (idx, _, text) in changes.iter().cloned() {
+                        *list[idx].field_mut(field) = match text.is_empty() {
+                            true => None,
+                            false => Some(text),
+                        };
+                    }
+                }
+                if field == ItemField::Name {
+                    let list = self.view.current_list.clone();
+                    for (idx, old, _) in changes {
+                        self.rename_weight_overrides(&list, idx, &old);
+                    }
+                }
+                self.view.replace_find.clear();
+                self.view.replace_with.clear();
+            }
+            UpdateBulkImages(text) => {
+                self.view.bulk_images = text;
+            }
+            BulkAddImages(text) => {
+                let name = self.view.current_list.clone();
+                let urls: Vec<&str> = text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .collect();
+                let mut added = 0;
+                for url in urls.iter() {
+                    if self.list_is_full(&name) {
+                        break;
+                    }
+                    if let Some(list) = self.state.lists.get_mut(&name) {
+                        list.push(Item {
+                            name: name_from_url(url),
+                            image: Some((*url).to_owned()),
+                            ..Default::default()
+                        });
+                        added += 1;
+                    }
+                }
+                if added < urls.len() {
+                    self.toast(format!(
+                        "Added {} of {} images; list {} is full",
+                        added,
+                        urls.len(),
+                        name
+                    ));
+                } else {
+                    self.view.bulk_images.clear();
+                }
+            }
+            FocusItem(idx) => {
+                self.view.current_item = Some(idx);
+                self.view.item_problems.clear();
+                self.check_image();
+                self.check_link();
+                self.open_item_editor();
+            }
+            BlurItem => {
+                self.view.current_item = None;
+                self.view.item_problems.clear();
+                self.image_check = None;
+                self.close_item_editor();
+            }
+            AddToGroup(entry) => {
+                let own = GroupEntry::Group {
+                    group: self.view.current_group.clone(),
+                };
+                if entry != own {
+                    if let Some(group) = self.state.groups.get_mut(&self.view.current_group) {
+                        group.push(entry);
+                    }
+                }
+            }
+            UpdateListName(text) => {
+                self.view.new_list_name = text;
+            }
+            EditListMax(text) => {
+                if self.state.lists.contains_key(&self.view.current_list) {
+                    if text.is_empty() {
+                        self.state.list_max.remove(&self.view.current_list);
+                    } else if let Ok(max) = text.parse::<usize>() {
+                        self.state
+                            .list_max
+                            .insert(self.view.current_list.clone(), max);
+                    }
+                }
+            }
+            EditListCooldown(text) => {
+                if self.state.lists.contains_key(&self.view.current_list) {
+                    let cooldown = match text.is_empty() {
+                        true => Some(0),
+                        false => text.parse::<usize>().ok(),
+                    };
+                    if let Some(cooldown) = cooldown {
+                        self.state
+                            .list_settings
+                            .entry(self.view.current_list.clone())
+                            .or_default()
+                            .cooldown = cooldown;
+                    }
+                }
+            }
+            EditListTemplate(text) => {
+                if self.state.lists.contains_key(&self.view.current_list) {
+                    self.state
+                        .list_settings
+                        .entry(self.view.current_list.clone())
+                        .or_default()
+                        .template = match text.trim().is_empty() {
+                        true => None,
+                        false => Some(text),
+                    };
+                }
+            }
+            ResetCooldowns(name) => {
+                if let Some(list) = self.state.lists.get_mut(&name) {
+                    for item in list.iter_mut() {
+                        item.cooldown = 0;
+                    }
+                }
+            }
+            ToggleRotation(name) => {
+                if self.state.lists.contains_key(&name) {
+                    let settings = self.state.list_settings.entry(name).or_default();
+                    settings.rotation = !settings.rotation;
+                }
+            }
+            SetRequired { list, required } => {
+                if self.state.lists.contains_key(&list) {
+                    self.state.list_settings.entry(list).or_default().required = required;
+                }
+            }
+            SetEmptyPolicy { list, policy } => {
+                if self.state.lists.contains_key(&list) {
+                    self.state.list_settings.entry(list).or_default().on_empty = policy;
+                }
+            }
+            SetDrawStyle { list, style } => {
+                if self.state.lists.contains_key(&list) {
+                    self.state.list_settings.entry(list).or_default().draw_style = style;
+                }
+            }
+            ToggleWarnMissing(name) => {
+                if self.state.lists.contains_key(&name) {
+                    let settings = self.state.list_settings.entry(name).or_default();
+                    settings.warn_missing = !settings.warn_missing;
+                }
+            }
+            SetDrawMode { list, mode } => {
+                if self.state.lists.contains_key(&list) {
+                    let settings = self.state.list_settings.entry(list).or_default();
+                    settings.bag.clear();
+                    settings.draw_mode = mode;
+                }
+            }
+            NormalizeWeights(name) => {
+                let target = match self.view.normalize_to_one && !self.uses_percentages(&name) {
+                    true => 1.0,
+                    false => 100.0,
+                };
+                let total: f64 = self.enabled_weights(&name).iter().sum();
+                // Two decimal places of the target, e.g. 12.34 of 100
+                let precision = 10_000.0 / target;
+                if total > 0.0 {
+                    self.reweigh(&name, |weight| {
+                        Some((weight * target / total * precision).round() / precision)
+                    });
+                }
+            }
+            SetNormalizeToOne(to_one) => {
+                self.view.normalize_to_one = to_one;
+            }
+            TogglePercentages(name) => {
+                if self.state.lists.contains_key(&name) {
+                    let enabled = self.enabled_weights(&name);
+                    let settings = self.state.list_settings.entry(name.clone()).or_default();
+                    settings.percentages = !settings.percentages;
+                    settings.tickets &= !settings.percentages;
+                    let percentages = settings.percentages;
+                    let total: f64 = enabled.iter().sum();
+                    // Scale so enabled items add up to 100, or back so they
+                    // average 1, the weight of an item with none set
+                    let scale = match percentages {
+                        true => 100.0 / total,
+                        false => enabled.len() as f64 / total,
+                    };
+                    if total > 0.0 {
+                        self.reweigh(&name, |weight| {
+                            match (weight * scale * 100.0).round() / 100.0 {
+                                w if w == 1.0 && !percentages => None,
+                                w => Some(w),
+                            }
+                        });
+                    }
+                }
+            }
+            ToggleNumeric(name) => {
+                let settings = self.state.list_settings.entry(name).or_default();
+                settings.numeric = match settings.numeric {
+                    Some(_) => None,
+                    None => Some(NumericList::default()),
+                };
+            }
+            EditNumeric { list, bound, text } => {
+                let value = match text.trim().parse::<i64>() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        self.toast("Enter a whole number.".to_owned());
+                        return true;
+                    }
+                };
+                let numeric = self
+                    .state
+                    .list_settings
+                    .get_mut(&list)
+                    .and_then(|settings| settings.numeric.as_mut());
+                if let Some(numeric) = numeric {
+                    match bound {
+                        NumericBound::Min => numeric.min = value,
+                        NumericBound::Max => numeric.max = value,
+                        NumericBound::Step => numeric.step = value.max(1),
+                    }
+                }
+            }
+            ToggleTickets(name) => {
+                if self.state.lists.contains_key(&name) {
+                    let settings = self.state.list_settings.entry(name.clone()).or_default();
+                    settings.tickets = !settings.tickets;
+                    if settings.tickets {
+                        settings.percentages = false;
+                        // Whole tickets, and at least one for anything that
+                        // could be drawn before
+                        self.reweigh(&name, |weight| {
+                            Some(match weight > 0.0 {
+                                true => weight.round().max(1.0),
+                                false => 0.0,
+                            })
+                        });
+                    }
+                }
+            }
+            StepTickets { idx, delta } => {
+                let list = self.view.current_list.clone();
+                let tickets = match self.state.lists.get(&list).and_then(|list| list.get(idx)) {
+                    Some(item) => self.item_weight(&list, item).round() + delta as f64,
+                    None => return false,
+                };
+                // A tiered item's own weight is ignored, so stepping takes it
+                // out of its tier
+                if let Some(item) = self
+                    .get_current_list_mut()
+                    .and_then(|list| list.get_mut(idx))
+                {
+                    item.tier = None;
+                    item.weight = Some(tickets.max(0.0));
+                }
+            }
+            ToggleConsumeOnRoll(name) => {
+                if self.state.lists.contains_key(&name) {
+                    let settings = self.state.list_settings.entry(name).or_default();
+                    settings.consume_on_roll = !settings.consume_on_roll;
+                }
+            }
+            EditListIcon(text) => {
+                if self.state.lists.contains_key(&self.view.current_list) {
+                    if text.is_empty() {
+                        self.state.list_icons.remove(&self.view.current_list);
+                    } else {
+                        self.state
+                            .list_icons
+                            .insert(self.view.current_list.clone(), text);
+                    }
+                }
+            }
+            EditItemName(text) => {
+                let old = self
+                    .get_current_index_and_item()
+                    .and_then(|(idx, item)| Some((idx, item.name.clone()?)));
+                if let Some(item) = self.get_current_item_mut() {
                     item.name = match text.is_empty() {
                         true => None,
                         false => Some(text),
                     };
                 }
+                if let Some((idx, old)) = old {
+                    let list = self.view.current_list.clone();
+                    self.rename_weight_overrides(&list, idx, &old);
+                }
             }
             EditItemImage(text) => {
                 if let Some(item) = self.get_current_item_mut() {
@@ -281,6 +2250,7 @@ impl Component for App {
                         false => Some(text),
                     };
                 }
+                self.check_image();
             }
             EditItemLink(text) => {
                 if let Some(item) = self.get_current_item_mut() {
@@ -289,6 +2259,130 @@ impl Component for App {
                         false => Some(text),
                     };
                 }
+                self.check_link();
+            }
+            ImageChecked { url, loaded } => {
+                let current = self
+                    .get_current_index_and_item()
+                    .and_then(|(_, item)| item.image.clone());
+                if current.as_ref() != Some(&url) {
+                    return false;
+                }
+                self.image_check = None;
+                if loaded {
+                    self.view.item_problems.remove("image");
+                } else {
+                    self.view
+                        .item_problems
+                        .insert("image", "This image doesn't load.".to_owned());
+                }
+            }
+            LinkSaved => {
+                if self.state.fetch_link_details {
+                    self.fetch_link_details();
+                }
+                return false;
+            }
+            LinkFetched {
+                list,
+                idx,
+                link,
+                page,
+            } => {
+                self._fetch_link = None;
+                let page = match page {
+                    Some(page) => page,
+                    None => {
+                        self.toast(format!(
+                            "Couldn't fetch {}. The site may not allow it.",
+                            link
+                        ));
+                        return true;
+                    }
+                };
+                let icon = page::icon_href(&page).unwrap_or_else(|| "/favicon.ico".to_owned());
+                let icon = Url::new_with_base(&icon, &link).map(|url| url.href()).ok();
+                let item = self
+                    .state
+                    .lists
+                    .get_mut(&list)
+                    .and_then(|list| list.get_mut(idx))
+                    .filter(|item| item.link.as_ref() == Some(&link));
+                if let Some(item) = item {
+                    if item.name.is_none() {
+                        item.name = page::title(&page);
+                    }
+                    if item.image.is_none() {
+                        item.image = icon;
+                    }
+                }
+            }
+            ToggleFetchLinkDetails => {
+                self.state.fetch_link_details = !self.state.fetch_link_details;
+            }
+            ToggleItemEditorDialog => {
+                self.state.item_editor_dialog = !self.state.item_editor_dialog;
+                self.view.edit_modal_open = false;
+                self.open_item_editor();
+            }
+            ToggleFairDraws => {
+                self.state.fair_draws = !self.state.fair_draws;
+            }
+            ToggleAutofreeze => {
+                self.state.autofreeze = !self.state.autofreeze;
+            }
+            EditHistoryTtl(text) => {
+                let days = match text.trim() {
+                    "" => Some(0),
+                    text => text.parse::<u32>().ok(),
+                };
+                if let Some(days) = days {
+                    self.state.history_ttl_days = days;
+                    self.expire_history();
+                }
+            }
+            VerifyDraw(idx) => {
+                let message = match self.state.history.get(idx) {
+                    Some(Draw {
+                        proof: Some(proof), ..
+                    }) => {
+                        let weights: Vec<f64> = proof.candidates.iter().map(|(_, w)| *w).collect();
+                        let mut rng = ChaCha20Rng::seed_from_u64(proof.seed);
+                        let rerun = (0..proof.attempts.max(1))
+                            .map(|_| weighted_index(&weights, &mut rng))
+                            .last()
+                            .flatten();
+                        match rerun {
+                            Some(winner) if winner == proof.winner => format!(
+                                "Verified: seed {} picks {} again",
+                                proof.seed,
+                                item_label(&proof.candidates[winner].0)
+                            ),
+                            _ => format!("Seed {} does not reproduce this draw", proof.seed),
+                        }
+                    }
+                    _ => "This draw has no recorded seed".to_owned(),
+                };
+                self.toast(message);
+            }
+            EditItemTags(text) => {
+                if let Some(item) = self.get_current_item_mut() {
+                    item.tags = comma_separated(&text);
+                }
+            }
+            EditItemAliases(text) => {
+                if let Some(item) = self.get_current_item_mut() {
+                    item.aliases = comma_separated(&text);
+                }
+            }
+            EditItemClass(text) => {
+                if let Some(item) = self.get_current_item_mut() {
+                    let class = sanitize_class(&text);
+                    item.css_class = match class.is_empty() {
+                        true => None,
+                        false => Some(class),
+                    };
+                }
             }
             EditItemComment(text) => {
                 if let Some(item) = self.get_current_item_mut() {
@@ -298,6 +2392,169 @@ impl Component for App {
                     };
                 }
             }
+            EditItemValue(text) => {
+                let value = match text.trim() {
+                    "" => Some(None),
+                    text => text
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite())
+                        .map(Some),
+                };
+                match value {
+                    Some(_) => self.view.item_problems.remove("value"),
+                    None => self
+                        .view
+                        .item_problems
+                        .insert("value", "Value must be a number.".to_owned()),
+                };
+                if let (Some(value), Some(item)) = (value, self.get_current_item_mut()) {
+                    item.value = value;
+                }
+            }
+            EditItemWeight(text) => {
+                let text = text.trim();
+                // "inf" and "1e400" parse, but aren't weights
+                let weight = text.parse::<f64>().ok().filter(|weight| weight.is_finite());
+                let max = match self.uses_percentages(&self.view.current_list) {
+                    true => 100.0,
+                    false => f64::MAX,
+                };
+                let tickets = self.uses_tickets(&self.view.current_list);
+                let problem = match weight {
+                    _ if text.is_empty() => None,
+                    None => Some("Weight must be a number."),
+                    Some(weight) if weight < 0.0 => Some("Weights below 0 count as 0."),
+                    Some(weight) if weight > max => Some("Percentages above 100 count as 100."),
+                    Some(weight) if tickets && weight.fract() != 0.0 => {
+                        Some("Tickets are whole numbers, so this rounds.")
+                    }
+                    Some(_) => None,
+                };
+                match problem {
+                    Some(problem) => self.view.item_problems.insert("weight", problem.to_owned()),
+                    None => self.view.item_problems.remove("weight"),
+                };
+                if let Some(item) = self.get_current_item_mut() {
+                    if text.is_empty() {
+                        item.weight = None;
+                    } else if let Some(weight) = weight {
+                        let weight = weight.clamp(0.0, max);
+                        item.weight = Some(match tickets {
+                            true => weight.round(),
+                            false => weight,
+                        });
+                    }
+                }
+            }
+            ToggleItemEnabled => {
+                if let Some(item) = self.get_current_item_mut() {
+                    item.enabled = !item.enabled;
+                }
+            }
+            ToggleHideInFlash => {
+                if let Some(item) = self.get_current_item_mut() {
+                    item.hide_in_flash = !item.hide_in_flash;
+                }
+            }
+            SetItemTier(tier) => {
+                if let Some(item) = self.get_current_item_mut() {
+                    item.tier = tier;
+                }
+            }
+            EditTierWeight { list, tier, text } => {
+                let weight = match text.parse::<f64>() {
+                    Ok(weight) if weight.is_finite() => weight.max(0.0),
+                    _ => return false,
+                };
+                if self.state.lists.contains_key(&list) {
+                    let tiers = self.tiers(&list);
+                    let settings = self.state.list_settings.entry(list).or_default();
+                    settings.tiers = tiers;
+                    if let Some(tier) = settings.tiers.get_mut(tier) {
+                        tier.weight = weight;
+                    }
+                }
+            }
+            ClearItem => {
+                if let Some(item) = self.get_current_item_mut() {
+                    *item = Item::default();
+                }
+                self.view.item_problems.clear();
+                self.image_check = None;
+            }
+            TogglePinned(idx) => {
+                if let Some(item) = self
+                    .get_current_list_mut()
+                    .and_then(|list| list.get_mut(idx))
+                {
+                    item.pinned = !item.pinned;
+                }
+            }
+            TogglePositionLocked(idx) => {
+                if let Some(item) = self
+                    .get_current_list_mut()
+                    .and_then(|list| list.get_mut(idx))
+                {
+                    item.position_locked = !item.position_locked;
+                }
+            }
+            SortList => {
+                let name = self.view.current_list.clone();
+                self.rearrange(&name, |free, list| {
+                    free.sort_by_cached_key(|idx| {
+                        let name = list[*idx].name.as_ref().map(|name| name.to_lowercase());
+                        // Unnamed items last
+                        (name.is_none(), name)
+                    })
+                });
+            }
+            ShuffleList => {
+                let name = self.view.current_list.clone();
+                let mut rng = StdRng::from_rng(&mut *self.rng.borrow_mut()).unwrap();
+                self.rearrange(&name, |free, _| free.shuffle(&mut rng));
+            }
+            CopyItemToList { idx, target } => {
+                let item = self
+                    .get_current_list()
+                    .and_then(|list| list.get(idx))
+                    .cloned();
+                if let Some(item) = item {
+                    if !self.can_copy_to(&item, &target) {
+                        return false;
+                    }
+                    if let Some(list) = self.state.lists.get_mut(&target) {
+                        list.push(Item {
+                            last_chosen: None,
+                            cooldown: 0,
+                            ..item
+                        });
+                        self.toast(format!("Copied to {}", self.list_label(&target)));
+                    }
+                }
+            }
+            ToggleItemWeekday(day) => {
+                if let Some(item) = self.get_current_item_mut() {
+                    let mask = item.weekdays.unwrap_or(ALL_WEEKDAYS) ^ (1 << day);
+                    item.weekdays = match mask {
+                        ALL_WEEKDAYS => None,
+                        mask => Some(mask),
+                    };
+                }
+            }
+            EditGroupBackground(text) => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    if text.is_empty() {
+                        self.state
+                            .group_backgrounds
+                            .remove(&self.view.current_group);
+                    } else {
+                        self.state
+                            .group_backgrounds
+                            .insert(self.view.current_group.clone(), text);
+                    }
+                }
+            }
             UpdateGroupName(text) => {
                 self.view.new_group_name = text;
             }
@@ -307,71 +2564,1275 @@ impl Component for App {
                     .confirm(&format!("Really delete list {}?", name))
                 {
                     let removed = self.state.lists.remove(&name);
-                    if removed.is_some() {
-                        for (_, group) in self.state.groups.iter_mut() {
-                            while let Some(idx) = group.iter().position(|x| *x == name) {
-                                group.remove(idx);
-                            }
+                    let icon = self.state.list_icons.remove(&name);
+                    let settings = self.state.list_settings.remove(&name);
+                    let max = self.state.list_max.remove(&name);
+                    let order = self.state.list_order.iter().position(|x| *x == name);
+                    self.state.list_order.retain(|x| *x != name);
+                    if let Some(items) = removed {
+                        let memberships = self.remove_from_groups(&GroupEntry::List(name.clone()));
+                        let label = format!("list {}", name);
+                        self.deleted(
+                            label,
+                            Deleted::List {
+                                name,
+                                items,
+                                icon,
+                                settings,
+                                max,
+                                order,
+                                memberships,
+                            },
+                        );
+                    }
+                }
+            }
+            RemoveListItem(idx) => {
+                let name = self.view.current_list.clone();
+                if let Some(item) = self.trash_item(name.clone(), idx) {
+                    let memberships = self.apply_empty_policy(&name);
+                    self.deleted(
+                        deleted_label(item_label(&item.name), &name, &memberships),
+                        Deleted::Items {
+                            list: name,
+                            items: vec![(idx, item)],
+                            memberships,
+                        },
+                    );
+                }
+            }
+            DragList(name) => {
+                self.view.dragging_list = Some(name);
+                return false;
+            }
+            DropList(target) => {
+                if let Some(dragged) = self.view.dragging_list.take() {
+                    let order = ordered(&self.state.list_order, &self.state.lists)
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    self.state.list_order = move_before(order, &dragged, &target);
+                }
+            }
+            DragGroup(name) => {
+                self.view.dragging_group = Some(name);
+                return false;
+            }
+            DropGroup(target) => {
+                if let Some(dragged) = self.view.dragging_group.take() {
+                    let order = ordered(&self.state.group_order, &self.state.groups)
+                        .into_iter()
+                        .cloned()
+                        .collect();
+                    self.state.group_order = move_before(order, &dragged, &target);
+                }
+            }
+            ToggleSortByOdds => {
+                self.view.sort_by_odds = !self.view.sort_by_odds;
+            }
+            RankList(name) => {
+                let ranked = self.weighted_order(&name);
+                let drawable = self
+                    .effective_weights(&name)
+                    .iter()
+                    .filter(|weight| **weight > 0.0)
+                    .count();
+                self.view.ranking = Some((name, ranked, drawable));
+            }
+            CloseRanking => {
+                self.view.ranking = None;
+            }
+            RenameTag { from, to } => {
+                let to = to.trim().to_owned();
+                if to.is_empty() || to == from {
+                    return true;
+                }
+                if let Some(list) = self.get_current_list_mut() {
+                    for item in list.iter_mut() {
+                        if item.tags.contains(&from) {
+                            item.tags.retain(|tag| *tag != from && *tag != to);
+                            item.tags.push(to.clone());
                         }
                     }
                 }
+                if self.view.tag_filter.as_ref() == Some(&from) {
+                    self.view.tag_filter = Some(to);
+                }
             }
-            RemoveListItem(name) => {
-                self.state
-                    .lists
-                    .get_mut(&self.view.current_list)
-                    .map(|list| list.remove(name));
+            DeleteTag(tag) => {
+                let count = self
+                    .get_current_list()
+                    .into_iter()
+                    .flatten()
+                    .filter(|item| item.tags.contains(&tag))
+                    .count();
+                if !self
+                    .dialog
+                    .confirm(&format!("Remove the tag {} from {} items?", tag, count))
+                {
+                    return false;
+                }
+                if let Some(list) = self.get_current_list_mut() {
+                    for item in list.iter_mut() {
+                        item.tags.retain(|t| *t != tag);
+                    }
+                }
+                if self.view.tag_filter.as_ref() == Some(&tag) {
+                    self.view.tag_filter = None;
+                }
+            }
+            FilterByTag(tag) => {
+                self.view.tag_filter = tag;
+            }
+            ToggleListsByEdited => {
+                self.view.lists_by_edited = !self.view.lists_by_edited;
+            }
+            ToggleFocusList => {
+                self.view.focus_list = !self.view.focus_list;
+            }
+            ToggleMultiSelect => {
+                self.view.multi_select = !self.view.multi_select;
+                self.view.selected_items.clear();
+            }
+            ToggleSelectItem(idx) => {
+                if !self.view.selected_items.remove(&idx) {
+                    self.view.selected_items.insert(idx);
+                }
+            }
+            SelectAllItems => {
+                let len = self.get_current_list().map(Vec::len).unwrap_or_default();
+                self.view.selected_items = (0..len).collect();
+            }
+            SelectNoItems => {
+                self.view.selected_items.clear();
+            }
+            InvertSelection => {
+                let len = self.get_current_list().map(Vec::len).unwrap_or_default();
+                self.view.selected_items = (0..len)
+                    .filter(|idx| !self.view.selected_items.contains(idx))
+                    .collect();
+            }
+            RemoveSelectedItems => {
+                let name = self.view.current_list.clone();
+                let selected: Vec<usize> = self.view.selected_items.iter().cloned().collect();
+                // Highest index first so the rest stay valid
+                let mut items = vec![];
+                for idx in selected.into_iter().rev() {
+                    if let Some(item) = self.trash_item(name.clone(), idx) {
+                        items.push((idx, item));
+                    }
+                }
+                items.reverse();
+                if !items.is_empty() {
+                    let memberships = self.apply_empty_policy(&name);
+                    let label =
+                        deleted_label(format!("{} items", items.len()), &name, &memberships);
+                    self.deleted(
+                        label,
+                        Deleted::Items {
+                            list: name,
+                            items,
+                            memberships,
+                        },
+                    );
+                }
+            }
+            RestoreTrashed(idx) => {
+                // Undo takes its items back off the end of the trash
+                self.view.undo_delete = None;
+                if let Some((name, _)) = self.state.trashed.get(idx) {
+                    if self.list_is_full(name) {
+                        self.toast(self.full_message(name));
+                        return true;
+                    }
+                    let (name, item) = self.state.trashed.remove(idx);
+                    self.state.lists.entry(name).or_default().push(item);
+                }
+            }
+            DeleteTrashed(idx) => {
+                self.view.undo_delete = None;
+                if idx < self.state.trashed.len() {
+                    self.state.trashed.remove(idx);
+                }
             }
             RemoveGroup(name) => {
                 if self
                     .dialog
                     .confirm(&format!("Really delete group {}?", name))
                 {
-                    self.state.groups.remove(&name);
+                    let removed = self.state.groups.remove(&name);
+                    let background = self.state.group_backgrounds.remove(&name);
+                    let settings = self.state.group_settings.remove(&name);
+                    let order = self.state.group_order.iter().position(|x| *x == name);
+                    self.state.group_order.retain(|x| *x != name);
+                    if let Some(entries) = removed {
+                        let memberships = self.remove_from_groups(&GroupEntry::Group {
+                            group: name.clone(),
+                        });
+                        let label = format!("group {}", name);
+                        self.deleted(
+                            label,
+                            Deleted::Group {
+                                name,
+                                entries,
+                                background,
+                                settings,
+                                order,
+                                memberships,
+                            },
+                        );
+                    }
                 }
             }
             RemoveGroupItem(name) => {
-                self.state
-                    .groups
-                    .get_mut(&self.view.current_group)
-                    .map(|group| {
-                        while let Some(idx) = group.iter().position(|x| *x == name) {
-                            group.remove(idx);
-                        }
+                let group = self.view.current_group.clone();
+                if let Some(entries) = self.state.groups.get_mut(&group) {
+                    let positions: Vec<usize> = (0..entries.len())
+                        .filter(|idx| entries[*idx] == name)
+                        .collect();
+                    entries.retain(|x| *x != name);
+                    if !positions.is_empty() {
+                        let label = match &name {
+                            GroupEntry::List(list) => list.clone(),
+                            GroupEntry::Group { group } => group.clone(),
+                        };
+                        self.deleted(
+                            format!("{} from {}", label, group),
+                            Deleted::GroupEntry {
+                                group,
+                                entry: name,
+                                positions,
+                            },
+                        );
+                    }
+                }
+            }
+            RemoveDanglingRef(entry) => {
+                for (_, group) in self.state.groups.iter_mut() {
+                    group.retain(|x| *x != entry);
+                }
+            }
+            PruneDanglingRefs => {
+                let lists = &self.state.lists;
+                let names: BTreeSet<String> = self.state.groups.keys().cloned().collect();
+                for (_, group) in self.state.groups.iter_mut() {
+                    group.retain(|entry| match entry {
+                        GroupEntry::List(name) => lists.contains_key(name),
+                        GroupEntry::Group { group } => names.contains(group),
                     });
+                }
+            }
+            FreezeList(_)
+            | RerollList(_)
+            | ThawList(_)
+            | ThawAllLists
+            | UndoPick
+            | StartCountdown
+            | PreviewGroupRoll(_)
+            | RollGroup
+            | RollWithinCap
+            | ShowRecent(_)
+            | RollUntil { .. }
+            | ExcludeChosen(_)
+            | UndoDraw(_)
+                if self.board_locked() =>
+            {
+                return false;
+            }
+            RerollList(_) | ThawList(_) | ExcludeChosen(_) | UndoDraw(_) | ShowRecent(_)
+            | UndoPick
+                if self.pinned_target(&msg).is_some() =>
+            {
+                let name = self.pinned_target(&msg).unwrap_or_default();
+                self.toast(format!("Unpin {} first", name));
+                return true;
+            }
+            FreezeList(name) | RerollList(name) | RollUntil { list: name, .. }
+                if !self.confirm_missing(&name) =>
+            {
+                return false;
             }
             FreezeList(name) => {
-                let new = self.choose_from_list(&name);
-                self.view.cache.insert(name, new);
+                let mut undo = self.before_pick(&name);
+                let since = js_sys::Date::now();
+                self.draw(name.clone());
+                // Only once there is a new pick to show instead
+                if self.drew_since(&name, since) {
+                    let thawed = self.thaw_others(&name);
+                    if let Some(undo) = undo.as_mut() {
+                        undo.thawed = thawed;
+                    }
+                }
+                self.after_pick(undo);
+                self.celebrate(&name);
+            }
+            RerollList(name) => {
+                if self.view.cache.contains_key(&name) {
+                    let undo = self.before_pick(&name);
+                    if self.consumes_on_roll(&name) {
+                        self.undo_draw(&name);
+                    }
+                    self.draw(name.clone());
+                    self.after_pick(undo);
+                    self.celebrate(&name);
+                }
+            }
+            UndoPick => {
+                let undo = match self
+                    .view
+                    .pick_undo
+                    .get_mut(&self.view.current_group)
+                    .and_then(Vec::pop)
+                {
+                    Some(undo) => undo,
+                    None => return false,
+                };
+                self.undo_pick(undo);
+                self.drop_stale_pins();
+            }
+            RollUntil { list, predicate } => {
+                if self.view.cache.contains_key(&list) && self.consumes_on_roll(&list) {
+                    self.undo_draw(&list);
+                }
+                let picked = match self.draw_until(list.clone(), Some(&predicate)) {
+                    Some(attempts) => attempts,
+                    None => return true,
+                };
+                let item = self
+                    .view
+                    .cache
+                    .get(&list)
+                    .map(|chosen| item_label(&chosen.item.name))
+                    .unwrap_or_default();
+                self.toast(match picked {
+                    (attempts, true) => format!("Drew {} after {} rolls", item, attempts),
+                    (attempts, false) => format!(
+                        "Nothing {} in {} rolls; kept the last draw, {}",
+                        predicate.describe(),
+                        attempts,
+                        item
+                    ),
+                });
+            }
+            UpdateRollUntil(text) => {
+                self.view.roll_until = text;
+            }
+            SetRollUntilByWeight(by_weight) => {
+                self.view.roll_until_by_weight = by_weight;
+            }
+            UndoDraw(name) => {
+                if self.view.last_consumed.contains_key(&name) {
+                    self.undo_draw(&name);
+                    self.view.cache.remove(&name);
+                    self.drop_stale_pins();
+                }
+            }
+            UndoDelete => {
+                self.undo_delete();
             }
             ThawList(name) => {
                 self.view.cache.remove(&name);
+                self.view.spin_start = 0.0;
+                self.drop_stale_pins();
             }
-            ThawAllLists => {
-                self.view.cache.clear();
+            TogglePinnedPick(name) => {
+                if self.pick_pinned(&name) {
+                    self.view.pinned_picks.retain(|(_, list)| *list != name);
+                } else if self.view.cache.contains_key(&name) {
+                    self.view
+                        .pinned_picks
+                        .insert((self.view.current_group.clone(), name));
+                }
             }
-            Purge => {
-                if self
-                    .dialog
-                    .confirm("Really delete all saved lists and groups?")
-                {
-                    self.state = State::default();
-                    self.view = View::default();
+            ToggleTempExcluded { list, idx } => {
+                let excluded = self.view.temp_excluded.entry(list.clone()).or_default();
+                if !excluded.remove(&idx) {
+                    excluded.insert(idx);
+                }
+                if excluded.is_empty() {
+                    self.view.temp_excluded.remove(&list);
                 }
             }
-            Tick => {}
-            Nothing => {}
-        }
-        self.store_and_export();
-        true
-    }
-
-    fn view(&self) -> Html {
-        info!("rendered!");
-        html! {
-            <>
+            ClearTempExcluded(list) => {
+                self.view.temp_excluded.remove(&list);
+            }
+            ExcludeChosen(name) => {
+                let idx = self.view.cache.get(&name).and_then(|chosen| chosen.idx);
+                let item = match (self.state.lists.get_mut(&name), idx) {
+                    (Some(list), Some(idx)) => list.get_mut(idx),
+                    _ => None,
+                };
+                if let Some(item) = item {
+                    item.enabled = false;
+                    let label = item_label(&item.name);
+                    self.toast(format!(
+                        "{} won't be drawn until it is enabled again",
+                        label
+                    ));
+                }
+            }
+            ShowRecent(idx) => {
+                let draw = match self.state.history.get(idx) {
+                    Some(draw) => draw.clone(),
+                    None => return false,
+                };
+                let found = self.state.lists.get(&draw.list).and_then(|list| {
+                    list.iter()
+                        .position(|item| item.name == draw.item)
+                        .map(|idx| (idx, list[idx].clone()))
+                });
+                // Consumed or deleted since, so only the name is left
+                let chosen = match found {
+                    Some((idx, item)) => Chosen {
+                        item,
+                        idx: Some(idx),
+                    },
+                    None => Chosen {
+                        item: Item {
+                            name: draw.item,
+                            ..Default::default()
+                        },
+                        idx: None,
+                    },
+                };
+                self.view.cache.insert(draw.list.clone(), chosen);
+                self.view
+                    .revealed
+                    .insert((self.view.current_group.clone(), draw.list));
+            }
+            ToggleSurprise => {
+                self.view.surprise = !self.view.surprise;
+            }
+            RevealList(name) => {
+                self.view
+                    .revealed
+                    .insert((self.view.current_group.clone(), name.clone()));
+                self.celebrate(&name);
+            }
+            RevealAll => {
+                for list in self.group_lists(&self.view.current_group) {
+                    self.view
+                        .revealed
+                        .insert((self.view.current_group.clone(), list));
+                }
+            }
+            RevealNext => {
+                let hidden = self.hidden_lists();
+                self.view.reveal_queue.retain(|name| hidden.contains(name));
+                if self.view.reveal_queue.is_empty() {
+                    self.view.reveal_queue = self.reveal_queue(hidden);
+                }
+                if !self.view.reveal_queue.is_empty() {
+                    let next = self.view.reveal_queue.remove(0);
+                    self.view
+                        .revealed
+                        .insert((self.view.current_group.clone(), next.clone()));
+                    self.celebrate(&next);
+                }
+            }
+            SetRevealOrder(order) => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    self.state
+                        .group_settings
+                        .entry(self.view.current_group.clone())
+                        .or_default()
+                        .reveal_order = order;
+                    self.view.reveal_queue.clear();
+                }
+            }
+            ToggleCelebrate => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    let settings = self
+                        .state
+                        .group_settings
+                        .entry(self.view.current_group.clone())
+                        .or_default();
+                    settings.celebrate = !settings.celebrate;
+                }
+            }
+            ThawAllLists => {
+                let pinned = &self.view.pinned_picks;
+                self.view
+                    .cache
+                    .retain(|list, _| pinned.iter().any(|(_, pinned)| pinned == list));
+                self.view.spin_start = 0.0;
+            }
+            ExportGroupPicks(group) => {
+                let lists = self.group_lists(&group);
+                if !self.board_locked() {
+                    for list in lists.iter() {
+                        if !self.view.cache.contains_key(list) {
+                            self.draw(list.clone());
+                        }
+                    }
+                }
+                let lines: Vec<String> = lists
+                    .iter()
+                    .map(|list| {
+                        let pick = match self.view.cache.get(list).map(|chosen| &chosen.item) {
+                            Some(item) => match (item.name.as_ref(), item.link.as_ref()) {
+                                (Some(name), Some(link)) => format!("{} ({})", name, link),
+                                (None, Some(link)) => link.clone(),
+                                (name, None) => item_label(&name.cloned()),
+                            },
+                            None => "Not picked".to_owned(),
+                        };
+                        format!("{}: {}", self.list_label(list), pick)
+                    })
+                    .collect();
+                self.view.group_picks = Some(lines.join("\n"));
+            }
+            EditValueCap(text) => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    let cap = match text.trim() {
+                        "" => Some(None),
+                        text => text
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|cap| cap.is_finite())
+                            .map(Some),
+                    };
+                    if let Some(cap) = cap {
+                        self.state
+                            .group_settings
+                            .entry(self.view.current_group.clone())
+                            .or_default()
+                            .value_cap = cap;
+                    }
+                }
+            }
+            EditCountdown(text) => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    let seconds = match text.is_empty() {
+                        true => Some(None),
+                        false => text.parse::<u32>().ok().map(Some),
+                    };
+                    if let Some(seconds) = seconds {
+                        self.state
+                            .group_settings
+                            .entry(self.view.current_group.clone())
+                            .or_default()
+                            .countdown_seconds = seconds;
+                    }
+                }
+            }
+            EditGroupWeight { list, item, weight } => {
+                if !self.state.groups.contains_key(&self.view.current_group) {
+                    return false;
+                }
+                let weight = match weight.trim() {
+                    "" => None,
+                    text => match text.parse::<f64>() {
+                        Ok(weight) if weight.is_finite() => Some(weight.max(0.0)),
+                        _ => {
+                            self.toast("Weight must be a number.".to_owned());
+                            return true;
+                        }
+                    },
+                };
+                let settings = self
+                    .state
+                    .group_settings
+                    .entry(self.view.current_group.clone())
+                    .or_default();
+                let weights = settings.weights.entry(list.clone()).or_default();
+                match weight {
+                    Some(weight) => weights.insert(item, weight),
+                    None => weights.remove(&item),
+                };
+                if weights.is_empty() {
+                    settings.weights.remove(&list);
+                }
+            }
+            StartCountdown => {
+                let seconds = match self.countdown_seconds(&self.view.current_group) {
+                    Some(seconds) => seconds,
+                    None => return false,
+                };
+                self.view.countdown = Some(Countdown {
+                    group: self.view.current_group.clone(),
+                    remaining_ms: seconds as f64 * 1000.0,
+                    resumed: Some(js_sys::Date::now()),
+                });
+            }
+            PauseCountdown => {
+                let now = js_sys::Date::now();
+                if let Some(countdown) = self.view.countdown.as_mut() {
+                    match countdown.resumed {
+                        Some(_) => {
+                            countdown.remaining_ms = countdown.left(now);
+                            countdown.resumed = None;
+                        }
+                        None => countdown.resumed = Some(now),
+                    }
+                }
+            }
+            CancelCountdown => {
+                self.view.countdown = None;
+            }
+            PreviewGroupRoll(group) => {
+                self.view.roll_preview = Some(self.roll_preview(group));
+            }
+            RollGroup => {
+                let preview = match self.view.roll_preview.take() {
+                    Some(preview) if preview.group == self.view.current_group => preview,
+                    _ => return true,
+                };
+                for list in preview.roll {
+                    if !self.view.cache.contains_key(&list) {
+                        self.update(FreezeList(list));
+                    }
+                }
+            }
+            RollWithinCap => {
+                let cap = match self.value_cap(&self.view.current_group) {
+                    Some(cap) => cap,
+                    None => return false,
+                };
+                // Only one pick would be left to count
+                if self.reveals_exclusively(&self.view.current_group) {
+                    self.toast("Roll Within Cap needs every pick showing, and this group shows one at a time".to_owned());
+                    return true;
+                }
+                self.roll_within_cap(cap);
+            }
+            ToggleGroupLock => {
+                let group = self.view.current_group.clone();
+                if !self.view.group_locked.remove(&group) {
+                    self.view.group_locked.insert(group);
+                }
+            }
+            EditSpinDuration(text) => {
+                if let Ok(seconds) = text.parse::<f64>() {
+                    self.state.spin.duration_ms = seconds.max(0.0) * 1000.0;
+                } else if text.is_empty() {
+                    self.state.spin.duration_ms = 0.0;
+                }
+                self.view.spin_start = 0.0;
+            }
+            SetSpinEasing(easing) => {
+                self.state.spin.easing = easing;
+                self.view.spin_start = 0.0;
+            }
+            OpenGroupLinks => {
+                let links = self.current_group_links();
+                if !links.is_empty()
+                    && self.dialog.confirm(&format!(
+                        "Open {} links in new tabs? If only some of them open, your browser \
+                         is blocking popups; allow popups for this page and try again.",
+                        links.len()
+                    ))
+                {
+                    if let Some(window) = web_sys::window() {
+                        for url in links {
+                            let _ = window.open_with_url_and_target_and_features(
+                                &url,
+                                "_blank",
+                                "noopener,noreferrer",
+                            );
+                        }
+                    }
+                }
+            }
+            RepairData => {
+                let mut fixed = self.state.repair();
+                fixed.extend(self.repair_view());
+                for fix in fixed.iter() {
+                    warn!("Repaired board: {}", fix);
+                }
+                self.toast(match fixed.len() {
+                    0 => "Nothing needed repairing".to_owned(),
+                    _ => format!("Repaired: {}", fixed.join("; ")),
+                });
+            }
+            Purge => {
+                let answer = utils::prompt(
+                    "This deletes all saved lists and groups. Type DELETE to confirm.",
+                );
+                if answer.as_deref() == Some("DELETE") {
+                    self.state = State::default();
+                    self.view = View::default();
+                }
+            }
+            ResetView => {
+                if !self.dialog.confirm(
+                    "Reset the view? Selections, filters and frozen picks are cleared, but lists, groups, locks and pinned picks are kept.",
+                ) {
+                    return false;
+                }
+                let first_list = ordered(&self.state.list_order, &self.state.lists)
+                    .first()
+                    .map(|name| (*name).clone())
+                    .unwrap_or_default();
+                let first_group = ordered(&self.state.group_order, &self.state.groups)
+                    .first()
+                    .map(|name| (*name).clone())
+                    .unwrap_or_default();
+                self.close_preview();
+                self.image_check = None;
+                self.edit_modal_return = None;
+                let mut view = View::new(first_list, first_group);
+                // What the user asked to keep, and another tab's save that
+                // is still waiting to be applied
+                let pinned = &self.view.pinned_picks;
+                view.cache = std::mem::take(&mut self.view.cache)
+                    .into_iter()
+                    .filter(|(list, _)| pinned.iter().any(|(_, pinned)| pinned == list))
+                    .collect();
+                view.pinned_picks = std::mem::take(&mut self.view.pinned_picks);
+                view.group_locked = std::mem::take(&mut self.view.group_locked);
+                view.external_state = self.view.external_state.take();
+                view.saves = self.view.saves;
+                self.view = view;
+                self.toast("View reset. Your lists and groups are unchanged.".to_owned());
+            }
+            LoadSampleData => {
+                if self.state.lists.is_empty() && self.state.groups.is_empty() {
+                    self.state = sample_state();
+                    self.view = View::for_state(&self.state);
+                    self.toast("Loaded the example board. Purge Everything clears it.".to_owned());
+                }
+            }
+            DismissSample => {
+                self.view.offer_sample = false;
+            }
+            UpdateOutline(text) => {
+                self.view.outline = text;
+            }
+            ImportOutline(text) => {
+                self.start_import(Import::Outline(text));
+            }
+            UpdateJsonImport(text) => {
+                self.view.json_import = text;
+            }
+            ImportJson(text) => {
+                self.start_import(Import::Json(text));
+            }
+            BoardFetched { url, board } => {
+                self._fetch_board = None;
+                // A reload shouldn't fetch it and ask all over again
+                utils::remove_query_param("import");
+                match board {
+                    Ok(text) => self.start_import(Import::Json(text)),
+                    Err(reason) => self
+                        .dialog
+                        .alert(&format!("Couldn't load the board at {}. {}", url, reason)),
+                }
+            }
+            OpenShareLink(board) => {
+                match utils::base64url_decode(&board)
+                    .and_then(|bytes| {
+                        miniz_oxide::inflate::decompress_to_vec_with_limit(
+                            &bytes,
+                            SHARE_LINK_MAX_BYTES,
+                        )
+                        .ok()
+                    })
+                    .and_then(|json| String::from_utf8(json).ok())
+                {
+                    Some(text) => self.start_import(Import::Json(text)),
+                    None => self.dialog.alert(
+                        "Couldn't load the shared board. The link is damaged, was cut short or holds \
+                         more than a board could.",
+                    ),
+                }
+            }
+            RunImport => {
+                self._busy = None;
+                match self.pending_import.take() {
+                    Some(Import::Outline(text)) => self.import_outline(&text),
+                    Some(Import::Json(text)) => self.import_json(&text),
+                    None => {}
+                }
+                self.view.busy = false;
+            }
+            RestoreScroll => {
+                self._restore_scroll = None;
+                self.restore_scroll();
+                return false;
+            }
+            StorageChanged { key, data } => {
+                if key != board_key(&self.boards.current) {
+                    return false;
+                }
+                self.view.external_state = Some((data, self.view.saves));
+                if !self.apply_external_state() {
+                    return false;
+                }
+            }
+            MirrorState(data) => {
+                // Posted as [board, state], so another board's saves are ignored
+                let state = match serde_json::from_str::<(String, String)>(&data) {
+                    Ok((board, state)) if board == self.boards.current => state,
+                    _ => return false,
+                };
+                if let Json(Ok(state)) = Ok(state).into() {
+                    self.state = state;
+                    self.view.list_snapshots = None;
+                }
+            }
+            ShowTab(tab) => self.view.mobile_tab = tab,
+            SwipeStart(e) => {
+                let touches = e.touches();
+                self.view.swipe = match touches.get(0) {
+                    Some(touch) if touches.length() == 1 && !starts_on_scrollable(&e) => {
+                        let at = (touch.client_x(), touch.client_y());
+                        Some(Swipe {
+                            start: at,
+                            last: at,
+                        })
+                    }
+                    _ => None,
+                };
+                return false;
+            }
+            SwipeMove(e) => {
+                if let (Some(swipe), Some(touch)) = (self.view.swipe.as_mut(), e.touches().get(0)) {
+                    swipe.last = (touch.client_x(), touch.client_y());
+                }
+                return false;
+            }
+            SwipeEnd => {
+                let swipe = match self.view.swipe.take() {
+                    Some(swipe) => swipe,
+                    None => return false,
+                };
+                let dx = swipe.last.0 - swipe.start.0;
+                let dy = swipe.last.1 - swipe.start.1;
+                // Mostly sideways, so scrolling down the page doesn't count
+                if dx.abs() < SWIPE_DISTANCE || dx.abs() < dy.abs() * 2 {
+                    return false;
+                }
+                let idx = MOBILE_TABS
+                    .iter()
+                    .position(|(tab, _)| *tab == self.view.mobile_tab)
+                    .unwrap_or_default();
+                // Swiping left brings in the tab to the right
+                let next = match dx < 0 {
+                    true => idx.checked_add(1),
+                    false => idx.checked_sub(1),
+                };
+                match next.and_then(|next| MOBILE_TABS.get(next)) {
+                    Some((tab, _)) => self.view.mobile_tab = *tab,
+                    None => return false,
+                }
+            }
+            KeyDown(e) => {
+                if self.embed.is_some() {
+                    return false;
+                }
+                let typing = e
+                    .target()
+                    .and_then(|target| target.dyn_into::<Element>().ok())
+                    .map(|el| {
+                        let tag = el.tag_name();
+                        tag == "INPUT" || tag == "TEXTAREA" || tag == "SELECT"
+                    })
+                    .unwrap_or_default();
+                let key = e.key();
+                // While the item editor is a dialog, Tab stays inside it and
+                // only Escape, which closes it, works as a shortcut
+                if self.edit_modal_showing() {
+                    if key == "Tab" {
+                        self.trap_focus(&e);
+                    }
+                    if key != "Escape" {
+                        return false;
+                    }
+                }
+                // Space only means something over a pick; elsewhere it
+                // scrolls the page as usual
+                if key == " " && self.view.hovered_list.is_none() {
+                    return false;
+                }
+                match SHORTCUTS.iter().find(|shortcut| {
+                    shortcut.key == key
+                        && shortcut.alt == e.alt_key()
+                        && (shortcut.while_typing || !typing)
+                }) {
+                    Some(shortcut) => {
+                        e.prevent_default();
+                        return self.update((shortcut.msg)());
+                    }
+                    None => return false,
+                }
+            }
+            HoverList(name) => {
+                self.view.hovered_list = name;
+                return false;
+            }
+            ToggleHoveredList => {
+                // Switching groups doesn't always fire a mouseleave
+                let name = match self.view.hovered_list.clone() {
+                    Some(name) if self.group_lists(&self.view.current_group).contains(&name) => {
+                        name
+                    }
+                    _ => return false,
+                };
+                return match self.view.cache.contains_key(&name) {
+                    true => self.update(ThawList(name)),
+                    false => self.update(FreezeList(name)),
+                };
+            }
+            ToggleHelp => {
+                self.view.show_help = !self.view.show_help;
+            }
+            ShowSettings => {
+                self.view.show_settings = true;
+            }
+            ResetSettings => {
+                if !self.dialog.confirm(
+                    "Put every setting back to its default? Lists, groups and history are kept.",
+                ) {
+                    return false;
+                }
+                let dialog = self.state.item_editor_dialog;
+                self.state.reset_settings();
+                // The item being edited would otherwise have nowhere to show
+                if dialog != self.state.item_editor_dialog {
+                    self.update(BlurItem);
+                }
+                self.toast("Settings reset to their defaults".to_owned());
+            }
+            ToggleExportImages => {
+                self.view.export_without_images = !self.view.export_without_images;
+                // Rebuild the export even though the state hasn't changed
+                self.view.cached_export.clear();
+            }
+            ExportHtml => {
+                self.view.exporting_html = true;
+                self._print = Some(
+                    self.render
+                        .request_animation_frame(self.link.callback(|_| Msg::ExportHtmlNow)),
+                );
+            }
+            ExportHtmlNow => {
+                self._print = None;
+                self.view.exporting_html = false;
+                let board = match self.snapshot_panel.cast::<Element>() {
+                    Some(panel) => panel.inner_html(),
+                    None => return true,
+                };
+                let page = self.snapshot_html(&board);
+                self.download(&page, "text/html", "html", "web page");
+            }
+            ExportReport => {
+                let report = self.roll_report();
+                self.download(&report, "text/markdown", "md", "report");
+                return false;
+            }
+            Print => {
+                self.view.printing = true;
+                self._print = Some(
+                    self.render
+                        .request_animation_frame(self.link.callback(|_| Msg::PrintNow)),
+                );
+            }
+            PrintNow => {
+                self._print = None;
+                // print() returns once the print dialog is closed
+                if let Some(window) = web_sys::window() {
+                    let _ = window.print();
+                }
+                self.view.printing = false;
+            }
+            ShowFullscreen(name) => {
+                self.view.fullscreen = Some(name);
+                self._enter_fullscreen = Some(
+                    self.render
+                        .request_animation_frame(self.link.callback(|_| Msg::EnterFullscreen)),
+                );
+            }
+            EnterFullscreen => {
+                self._enter_fullscreen = None;
+                // If the browser refuses, the overlay already fills the window
+                if let Some(panel) = self.fullscreen_panel.cast::<Element>() {
+                    let _ = panel.request_fullscreen();
+                }
+                return false;
+            }
+            FullscreenChanged(active) => {
+                if active && self.view.fullscreen.is_none() {
+                    // Granted after the overlay was already closed
+                    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                        document.exit_fullscreen();
+                    }
+                } else if active {
+                    self.view.fullscreen_active = true;
+                } else if self.view.fullscreen_active {
+                    // The browser handles Escape itself while in fullscreen
+                    self.view.fullscreen = None;
+                    self.view.fullscreen_active = false;
+                } else {
+                    // Refused, so the overlay stays as the fallback
+                    return false;
+                }
+            }
+            ReadBookmarks(file) => {
+                let callback = self.link.callback(Msg::BookmarksRead);
+                self._read_bookmarks = self.reader.read_file(file, callback).ok();
+                return false;
+            }
+            BookmarksRead(data) => {
+                self._read_bookmarks = None;
+                let folders = bookmarks::parse(&String::from_utf8_lossy(&data.content));
+                if folders.is_empty() {
+                    self.dialog
+                        .alert(&format!("{} has no bookmarks in it.", data.name));
+                    return false;
+                }
+                // Each folder goes into a list named after it, to start with
+                let folders = folders
+                    .into_iter()
+                    .map(|folder| {
+                        let list = folder
+                            .name
+                            .rsplit(" / ")
+                            .next()
+                            .unwrap_or_default()
+                            .to_owned();
+                        (folder, list)
+                    })
+                    .collect();
+                self.view.bookmark_import = Some(folders);
+            }
+            SetBookmarkList { folder, list } => {
+                if let Some((_, target)) = self
+                    .view
+                    .bookmark_import
+                    .as_mut()
+                    .and_then(|folders| folders.get_mut(folder))
+                {
+                    *target = list.trim().to_owned();
+                }
+            }
+            ImportBookmarks => {
+                let folders = match self.view.bookmark_import.take() {
+                    Some(folders) => folders,
+                    None => return false,
+                };
+                self.import_bookmarks(folders);
+            }
+            CancelBookmarks => {
+                self.view.bookmark_import = None;
+            }
+            ToggleExclusiveReveal => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    let settings = self
+                        .state
+                        .group_settings
+                        .entry(self.view.current_group.clone())
+                        .or_default();
+                    settings.exclusive_reveal = !settings.exclusive_reveal;
+                }
+            }
+            TogglePreviewLinks => {
+                if self.state.groups.contains_key(&self.view.current_group) {
+                    let settings = self
+                        .state
+                        .group_settings
+                        .entry(self.view.current_group.clone())
+                        .or_default();
+                    settings.preview_links = !settings.preview_links;
+                }
+            }
+            PreviewLink(url) => {
+                self.view.link_preview = Some(LinkPreview { url, loaded: false });
+                self._watch_preview = Some(
+                    self.render
+                        .request_animation_frame(self.link.callback(|_| Msg::WatchPreview)),
+                );
+            }
+            WatchPreview => {
+                // The frame stays put when another link is previewed, and
+                // already has its listener
+                if self._on_preview_load.is_some() {
+                    return false;
+                }
+                if let Some(frame) = self.preview_frame.cast::<Element>() {
+                    let callback = self.link.callback(|_| Msg::PreviewLoaded);
+                    let closure =
+                        Closure::wrap(Box::new(move || callback.emit(())) as Box<dyn FnMut()>);
+                    let _ = frame
+                        .add_event_listener_with_callback("load", closure.as_ref().unchecked_ref());
+                    self._on_preview_load = Some(closure);
+                }
+                return false;
+            }
+            PreviewLoaded => match self.view.link_preview.as_mut() {
+                Some(preview) => preview.loaded = true,
+                None => return false,
+            },
+            ClosePreview => {
+                self.close_preview();
+            }
+            FocusEditModal => {
+                self._focus_edit_modal = None;
+                if let Some(first) = self
+                    .edit_modal
+                    .cast::<Element>()
+                    .and_then(|modal| focusable_in(&modal).into_iter().next())
+                {
+                    let _ = first.focus();
+                }
+                return false;
+            }
+            CloseEditModal => {
+                return self.update(BlurItem);
+            }
+            RestoreEditFocus => {
+                self._focus_edit_modal = None;
+                if let Some(element) = self.edit_modal_return.take() {
+                    let _ = element.focus();
+                }
+                return false;
+            }
+            ShowQr => {
+                let payload = self.share_link().unwrap_or_default();
+                let code = QrCode::with_error_correction_level(payload.as_bytes(), EcLevel::L)
+                    .ok()
+                    .filter(|code| {
+                        matches!(code.version(), Version::Normal(version) if version <= QR_MAX_VERSION)
+                    });
+                match code {
+                    Some(code) => {
+                        self.view.qr = Some(code);
+                        self._draw_qr = Some(
+                            self.render
+                                .request_animation_frame(self.link.callback(|_| Msg::DrawQr)),
+                        );
+                    }
+                    None => {
+                        self.dialog.alert(&format!(
+                            "This board's share link is {} bytes, more than the {} a QR code \
+                             can hold and still be scanned. Use Export Data instead.",
+                            payload.len(),
+                            QR_CAPACITY
+                        ));
+                        return false;
+                    }
+                }
+            }
+            DrawQr => {
+                self.draw_qr();
+                return false;
+            }
+            CloseOverlay => {
+                if self.edit_modal_showing() {
+                    return self.update(BlurItem);
+                }
+                self.close_preview();
+                self.view.qr = None;
+                self.view.show_help = false;
+                self.view.show_settings = false;
+                self.view.group_picks = None;
+                self.view.roll_preview = None;
+                if self.view.fullscreen.take().is_some() && self.view.fullscreen_active {
+                    self.view.fullscreen_active = false;
+                    if let Some(document) = web_sys::window().and_then(|w| w.document()) {
+                        if document.fullscreen_element().is_some() {
+                            document.exit_fullscreen();
+                        }
+                    }
+                }
+            }
+            SwitchBoard(name) => {
+                if name != self.boards.current
+                    && (name.is_empty() || self.boards.names.contains(&name))
+                {
+                    self.switch_board(name);
+                }
+            }
+            UpdateBoardName(text) => {
+                self.view.new_board_name = text;
+            }
+            CreateBoard => {
+                let name = self.view.new_board_name.trim().to_owned();
+                if !name.is_empty() {
+                    self.boards.names.insert(name.clone());
+                    self.switch_board(name);
+                }
+            }
+            SearchItems(query) => {
+                self.view.search = query;
+            }
+            OpenSearchResult(list, idx) => {
+                self.remember_scroll();
+                self.view.selected_items.clear();
+                self.view.current_list = list;
+                self.view.current_item = Some(idx);
+                self.view.search.clear();
+                self.open_item_editor();
+            }
+            Tick => {
+                if self.spin_due() {
+                    self.roll_flash();
+                }
+                self.draw_confetti();
+                if self.view.external_state.is_some() {
+                    self.apply_external_state();
+                }
+                self.finish_countdown();
+                if !self.mirror {
+                    self.expire_history();
+                }
+                let now = js_sys::Date::now();
+                self.view
+                    .revealing
+                    .retain(|_, started| now - *started < REVEAL_MS);
+                if self.embed.is_some() {
+                    self.post_embed_height();
+                }
+                if let Some((_, expires)) = self.view.toast {
+                    if js_sys::Date::now() > expires {
+                        self.view.toast = None;
+                        self.view.undo_delete = None;
+                    }
+                }
+            }
+            Nothing => {}
+        }
+        self.store_and_export();
+        true
+    }
+
+    fn view(&self) -> Html {
+        info!("rendered!");
+        if self.view.printing {
+            return self.render_print();
+        }
+        if let Some(group) = self.embed.as_ref() {
+            return self.render_embed(group);
+        }
+        let mut board_class = MOBILE_TABS
+            .iter()
+            .find(|(tab, _)| *tab == self.view.mobile_tab)
+            .map(|(_, label)| format!("autospoon tab-{}", label.to_lowercase()))
+            .unwrap_or_default();
+        if self.view.focus_list && self.state.lists.contains_key(&self.view.current_list) {
+            board_class.push_str(" focus-list");
+        }
+        if self.mirror {
+            board_class.push_str(" read-only");
+        }
+        html! {
+            <>
             <h1>{"Automatic Spoon!"}</h1>
-            <div class={"autospoon"}>
+            {self.render_boards()}
+            {if self.mirror {
+                html! {<p class="mirror">{"Mirroring another tab. Make changes there."}</p>}
+            } else {
+                html! {}
+            }}
+            {self.render_sample_offer()}
+            {self.render_breadcrumb()}
+            {self.render_search()}
+            {self.render_tabs()}
+            <div class=board_class
+                ontouchstart=self.link.callback(Msg::SwipeStart)
+                ontouchmove=self.link.callback(Msg::SwipeMove)
+                ontouchend=self.link.callback(|_| Msg::SwipeEnd)
+            >
                 { self.render_groups()}
                 { self.render_group()}
                 { self.render_lists()}
@@ -381,9 +3842,44 @@ impl Component for App {
                     <button class="purge" onclick=self.link.callback(|_| Msg::Purge)>
                         {"Purge Everything"}
                     </button>
+                    <button title="Clear selections, filters and frozen picks without touching any data"
+                        onclick=self.link.callback(|_| Msg::ResetView)>
+                        {"Reset View"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::Print)>
+                        {"Print Board"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::RepairData)>
+                        {"Repair Data"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::ShowQr)>
+                        {"Show QR Code"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::ExportHtml)>
+                        {"Save as Web Page"}
+                    </button>
                     {self.render_export_link()}
+                    {self.render_outline_import()}
+                    {self.render_bookmark_import()}
+                    {self.render_json_import()}
+                    {self.render_trash()}
+                    {self.render_history()}
+                    <button onclick=self.link.callback(|_| Msg::ShowSettings)>
+                        {"Settings"}
+                    </button>
                 </div>
             </div>
+            {self.render_help()}
+            {self.render_settings()}
+            {self.render_group_picks()}
+            {self.render_roll_preview()}
+            {self.render_qr()}
+            {self.render_snapshot()}
+            {self.render_link_preview()}
+            {self.render_edit_modal()}
+            {self.render_fullscreen()}
+            {self.render_toast()}
+            {self.render_busy()}
             </>
         }
     }
@@ -391,27 +3887,256 @@ impl Component for App {
 
 impl App {
     fn store_and_export(&mut self) {
+        self.state.current_list = Some(self.view.current_list.clone());
+        self.state.current_group = Some(self.view.current_group.clone());
         let data: Text = Json(&self.state).into();
         if let Ok(data) = data {
             if data != self.view.cached_export {
+                let data = match self.stamp_modified_lists() {
+                    true => {
+                        let stamped: Text = Json(&self.state).into();
+                        stamped.unwrap_or(data)
+                    }
+                    false => data,
+                };
                 info!(
                     "{}\n{}\n{}",
                     &data,
                     &self.view.cached_export,
                     data == self.view.cached_export
                 );
-                self.storage.store(KEY, Ok(data.clone()));
+                if !self.mirror && self.embed.is_none() {
+                    self.storage
+                        .store(&board_key(&self.boards.current), Ok(data.clone()));
+                    self.view.saves += 1;
+                    let message: Text = Json(&(&self.boards.current, &data)).into();
+                    if let (Some(channel), Ok(message)) = (self.channel.as_ref(), message) {
+                        let _ = channel.post_message(&JsValue::from_str(&message));
+                    }
+                }
+                let export = match self.view.export_without_images {
+                    true => Json(&self.state_without_large_images()).into(),
+                    false => Ok(data.clone()),
+                };
                 let strings = Array::new();
-                strings.push(&data.clone().into());
+                strings.push(&export.unwrap_or_default().into());
 
                 let blob = Blob::new_with_str_sequence(&strings.into()).unwrap();
 
                 let url = Url::create_object_url_with_blob(&blob).unwrap_or_default();
-                self.view.export_url = Some(url);
+                if let Some(old) = self.view.export_url.replace(url) {
+                    let _ = Url::revoke_object_url(&old);
+                }
                 self.view.cached_export = data;
             }
         }
     }
+    // Record the time against every list changed since the last save.
+    // Returns whether any were.
+    fn stamp_modified_lists(&mut self) -> bool {
+        let snapshots: BTreeMap<String, String> = self
+            .state
+            .lists
+            .iter()
+            .map(|(name, items)| (name.clone(), list_snapshot(items)))
+            .collect();
+        let mut stamped = false;
+        if let Some(before) = self.view.list_snapshots.as_ref() {
+            let now = js_sys::Date::now();
+            for (name, snapshot) in snapshots.iter() {
+                if before.get(name) != Some(snapshot) {
+                    self.state.list_modified.insert(name.clone(), now);
+                    stamped = true;
+                }
+            }
+        }
+        let lists = &self.state.lists;
+        let before = self.state.list_modified.len();
+        self.state
+            .list_modified
+            .retain(|name, _| lists.contains_key(name));
+        stamped |= self.state.list_modified.len() != before;
+        self.view.list_snapshots = Some(snapshots);
+        stamped
+    }
+    // Switch to the board another tab saved, unless the user is typing in a
+    // field, whose text would jump about under them.  That waits for a later
+    // Tick.  Returns whether the board changed.
+    fn apply_external_state(&mut self) -> bool {
+        if editing_text() {
+            return false;
+        }
+        let (data, saves) = match self.view.external_state.take() {
+            Some(held) => held,
+            None => return false,
+        };
+        let mut state: State = match Ok(data).into() {
+            Json(Ok(state)) => state,
+            _ => return false,
+        };
+        // Each tab keeps its own selection.  Ignoring the other tab's means
+        // a save that only changed the selection is no change at all, so
+        // the two tabs don't keep saving over each other.
+        state.current_list = Some(self.view.current_list.clone());
+        state.current_group = Some(self.view.current_group.clone());
+        let data: Text = Json(&state).into();
+        if data.ok().as_ref() == Some(&self.view.cached_export) {
+            return false;
+        }
+        // Edits typed here since it arrived are newer than it, and already
+        // saved, so only load it over them if asked to
+        if self.view.saves != saves
+            && !self.dialog.confirm(
+                "Another tab saved this board while you were typing here. \
+                 Load its version instead of your latest edits?",
+            )
+        {
+            return false;
+        }
+        for fix in state.repair() {
+            warn!("Repaired board from another tab: {}", fix);
+        }
+        self.state = state;
+        self.view.list_snapshots = None;
+        // Undo would put back items from the board that was replaced
+        self.view.pick_undo.clear();
+        self.view.undo_delete = None;
+        self.view.last_consumed.clear();
+        self.repair_view();
+        true
+    }
+    // Every embedded image over LARGE_IMAGE_BYTES, in lists and the trash
+    fn large_images(&self) -> impl Iterator<Item = &String> {
+        self.state
+            .lists
+            .values()
+            .flatten()
+            .chain(self.state.trashed.iter().map(|(_, item)| item))
+            .filter_map(|item| item.image.as_ref())
+            .filter(|image| is_large_image(image))
+    }
+    fn close_preview(&mut self) {
+        self.view.link_preview = None;
+        self._watch_preview = None;
+        self._on_preview_load = None;
+    }
+    // A link that opens this board on another device.  What only matters on
+    // this one (history, trash, when items were last drawn) stays behind, and
+    // so does every field that loading fills back in, before the JSON is
+    // deflated into `?board=`.
+    fn share_link(&self) -> Option<String> {
+        let mut state = self.state_without_large_images();
+        state.history.clear();
+        state.trashed.clear();
+        state.list_modified.clear();
+        for item in state.lists.values_mut().flatten() {
+            item.last_chosen = None;
+            item.cooldown = 0;
+        }
+        for settings in state.list_settings.values_mut() {
+            settings.bag.clear();
+        }
+        let mut board = serde_json::to_value(&state).ok()?;
+        strip_defaults(&mut board);
+        let json = serde_json::to_string(&board).ok()?;
+        let location = web_sys::window()?.location();
+        Some(format!(
+            "{}{}?board={}",
+            location.origin().ok()?,
+            location.pathname().ok()?,
+            utils::base64url_encode(&miniz_oxide::deflate::compress_to_vec(json.as_bytes(), 9))?
+        ))
+    }
+    fn draw_qr(&self) {
+        let (code, canvas) = match (
+            self.view.qr.as_ref(),
+            self.qr_canvas.cast::<HtmlCanvasElement>(),
+        ) {
+            (Some(code), Some(canvas)) => (code, canvas),
+            _ => return,
+        };
+        let context = match canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        {
+            Some(context) => context,
+            None => return,
+        };
+        // Scanners want a light border four modules wide
+        let size = code.width();
+        let side = (size + QR_QUIET_ZONE * 2) as u32 * QR_MODULE_PX;
+        canvas.set_width(side);
+        canvas.set_height(side);
+        context.set_fill_style_str("white");
+        context.fill_rect(0.0, 0.0, side as f64, side as f64);
+        context.set_fill_style_str("black");
+        let module = QR_MODULE_PX as f64;
+        for y in 0..size {
+            for x in 0..size {
+                if code[(x, y)] == Color::Dark {
+                    let left = (x + QR_QUIET_ZONE) as f64 * module;
+                    let top = (y + QR_QUIET_ZONE) as f64 * module;
+                    context.fill_rect(left, top, module, module);
+                }
+            }
+        }
+    }
+    fn state_without_large_images(&self) -> State {
+        let mut state = self.state.clone();
+        let items = state
+            .lists
+            .values_mut()
+            .flatten()
+            .chain(state.trashed.iter_mut().map(|(_, item)| item));
+        for item in items {
+            if item
+                .image
+                .as_ref()
+                .map(|image| is_large_image(image))
+                .unwrap_or(false)
+            {
+                item.image = Some(EXCLUDED_IMAGE.to_owned());
+            }
+        }
+        state
+    }
+    fn switch_board(&mut self, name: String) {
+        self.boards.current = name;
+        self.storage.store(BOARDS_KEY, Json(&self.boards));
+        self.state = load_state(&self.storage, &self.boards.current);
+        self.view = View::for_state(&self.state);
+        self.expire_history();
+    }
+    fn scroll_keys(&self) -> [(String, &NodeRef); 2] {
+        [
+            (format!("list:{}", self.view.current_list), &self.list_panel),
+            (
+                format!("group:{}", self.view.current_group),
+                &self.group_panel,
+            ),
+        ]
+    }
+    fn remember_scroll(&mut self) {
+        let offsets: Vec<(String, i32)> = self
+            .scroll_keys()
+            .iter()
+            .filter_map(|(key, panel)| {
+                panel
+                    .cast::<Element>()
+                    .map(|el| (key.clone(), el.scroll_top()))
+            })
+            .collect();
+        self.view.scroll.extend(offsets);
+    }
+    fn restore_scroll(&self) {
+        for (key, panel) in self.scroll_keys().iter() {
+            if let Some(el) = panel.cast::<Element>() {
+                el.set_scroll_top(self.view.scroll.get(key).cloned().unwrap_or_default());
+            }
+        }
+    }
     fn get_current_list(&self) -> Option<&Vec<Item>> {
         self.state.lists.get(&self.view.current_list)
     }
@@ -419,248 +4144,4833 @@ impl App {
         self.state.lists.get_mut(&self.view.current_list)
     }
     fn get_current_item_mut(&mut self) -> Option<&mut Item> {
-        let maybe_index = self.view.current_item.clone();
+        let maybe_index = self.view.current_item;
         match (self.get_current_list_mut(), maybe_index) {
             (Some(list), Some(idx)) => list.get_mut(idx),
             _ => None,
         }
     }
     fn get_current_index_and_item(&self) -> Option<(usize, &Item)> {
-        let maybe_index = self.view.current_item.clone();
+        let maybe_index = self.view.current_item;
         match (self.get_current_list(), maybe_index) {
             (Some(list), Some(idx)) => list.get(idx).map(|item| (idx, item)),
             _ => None,
         }
     }
-    fn render_export_link(&self) -> Html {
-        if let Some(url) = self.view.export_url.as_ref().cloned() {
-            html! {
-                <a href=url target="_blank">{"Export Data"}</a>
-            }
-        } else {
-            html! {
-                <p>{"Error generating data export"}</p>
+    // Drop selections and picks that point past the end of their list
+    fn repair_view(&mut self) -> Vec<String> {
+        let mut fixed = Vec::new();
+        let lists = &self.state.lists;
+        let len = |name: &str| lists.get(name).map(Vec::len).unwrap_or_default();
+        if !self.view.current_list.is_empty() && !lists.contains_key(&self.view.current_list) {
+            self.view.current_list.clear();
+            fixed.push("deselected a list that no longer exists".to_owned());
+        }
+        if !self.view.current_group.is_empty()
+            && !self.state.groups.contains_key(&self.view.current_group)
+        {
+            self.view.current_group.clear();
+            fixed.push("deselected a group that no longer exists".to_owned());
+        }
+        let current = len(&self.view.current_list);
+        if self
+            .view
+            .current_item
+            .map(|idx| idx >= current)
+            .unwrap_or(false)
+        {
+            self.view.current_item = None;
+            fixed.push("deselected an item past the end of its list".to_owned());
+        }
+        let before = self.view.selected_items.len();
+        self.view.selected_items.retain(|idx| *idx < current);
+        if self.view.selected_items.len() != before {
+            fixed.push("unselected items past the end of the list".to_owned());
+        }
+        let before = self.view.flash.len();
+        self.view
+            .flash
+            .retain(|name, chosen| chosen.idx.map(|idx| idx < len(name)).unwrap_or(true));
+        if self.view.flash.len() != before {
+            fixed.push("stopped showing items past the end of their list".to_owned());
+        }
+        for (name, chosen) in self.view.cache.iter_mut() {
+            if chosen.idx.map(|idx| idx >= len(name)).unwrap_or(false) {
+                chosen.idx = None;
+                fixed.push(format!("unlinked the pick for {} from its list", name));
+            }
+        }
+        for (name, settings) in self.state.list_settings.iter_mut() {
+            let bag = &mut settings.bag;
+            let before = bag.len();
+            bag.retain(|idx| *idx < len(name));
+            if bag.len() != before {
+                fixed.push(format!("forgot draws past the end of {}", name));
             }
         }
+        fixed
     }
-    fn render_groups(&self) -> Html {
-        html! {
-            <div class="groups">
-            <p>{"Groups"}</p>
-            <ul>
-                {
-                    for self.state.groups.keys().map(|group| {
-                        let name = group.to_owned();
-                        let name2 = name.clone();
-                        let (class,callback) = if name == self.view.current_group {
-                            ("selected", self.link.callback(move |_| Msg::BlurGroup))
-                        } else {
-                            ("", self.link.callback(move |_| Msg::FocusGroup(name.clone())))
-                        };
-                        html! {
-                            <li
-                                class=class
-                                onclick=callback
-                            > {group}
-                                <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveGroup(name2.clone()))>
-                                    {"Delete"}
-                                </button>
-                            </li>
-                        }
-                    })
+    fn toast(&mut self, message: String) {
+        self.view.toast = Some((message, js_sys::Date::now() + TOAST_MS));
+        self.view.undo_delete = None;
+    }
+    // Take a list that has just lost its last item out of its groups, if
+    // that is its policy.  Returns where it was, for undo.  Only deleting
+    // items calls this: a draw that consumes the last item leaves the list
+    // in place, or the pick would vanish from the group as it froze and
+    // Undo Draw would have no group to put the item back into.
+    fn apply_empty_policy(&mut self, name: &str) -> Vec<(String, usize)> {
+        let empty = self
+            .state
+            .lists
+            .get(name)
+            .map(Vec::is_empty)
+            .unwrap_or(false);
+        match empty && self.on_empty(name) == EmptyPolicy::RemoveFromGroups {
+            true => self.remove_from_groups(&GroupEntry::List(name.to_owned())),
+            false => vec![],
+        }
+    }
+    fn deleted(&mut self, label: String, deleted: Deleted) {
+        self.toast(format!("Deleted {}", label));
+        self.view.undo_delete = Some(deleted);
+    }
+    // Take `entry` out of every group, returning each (group, index) it was at
+    fn remove_from_groups(&mut self, entry: &GroupEntry) -> Vec<(String, usize)> {
+        let mut memberships = vec![];
+        for (name, group) in self.state.groups.iter_mut() {
+            for (idx, x) in group.iter().enumerate() {
+                if x == entry {
+                    memberships.push((name.clone(), idx));
                 }
-                <li>
-                    <input class="edit"
-                        type="text"
-                        placeholder="New Group"
-                        value=&self.view.new_group_name
-                        oninput=self.link.callback(move |e: InputData| Msg::UpdateGroupName(e.value))
-                        onkeypress=self.link.callback(move |e: KeyboardEvent| {
-                            if e.key() == "Enter" { Msg::CreateGroup } else { Msg::Nothing }
-                    }) />
-                </li>
-            </ul>
-            </div>
+            }
+            group.retain(|x| x != entry);
         }
+        memberships
     }
-    fn render_group(&self) -> Html {
-        if let Some(group) = self.state.groups.get(&self.view.current_group) {
-            let name = self.view.current_group.to_owned();
-            html! {
-                <div class="group">
-                    <p>{&name}</p>
-                    <button onclick=self.link.callback(move |_| Msg::ThawAllLists)>
-                        {"Unlock All Lists"}
-                    </button>
-                    <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveGroup(name.clone()))>
-                        {"Delete Group"}
-                    </button>
-                    <dl>
-                        {for group.iter().map(|entry| { self.render_group_element(entry)})}
-                    </dl>
-                </div>
+    fn restore_to_groups(&mut self, entry: GroupEntry, memberships: Vec<(String, usize)>) {
+        for (name, idx) in memberships {
+            if let Some(group) = self.state.groups.get_mut(&name) {
+                group.insert(idx.min(group.len()), entry.clone());
             }
-        } else {
-            html! {
-                <div class="group">
-                </div>
+        }
+    }
+    fn undo_delete(&mut self) {
+        let deleted = match self.view.undo_delete.take() {
+            Some(deleted) => deleted,
+            None => return,
+        };
+        self.view.toast = None;
+        match deleted {
+            Deleted::Items {
+                list,
+                items,
+                memberships,
+            } => {
+                let len = self.state.lists.get(&list).map_or(0, Vec::len);
+                if matches!(self.state.list_max.get(&list), Some(max) if len + items.len() > *max) {
+                    self.toast(self.full_message(&list));
+                    return;
+                }
+                let count = items.len().min(self.state.trashed.len());
+                self.state
+                    .trashed
+                    .truncate(self.state.trashed.len() - count);
+                for (idx, item) in items {
+                    let items = self.state.lists.entry(list.clone()).or_default();
+                    let idx = idx.min(items.len());
+                    items.insert(idx, item);
+                    self.item_inserted(&list, idx);
+                }
+                self.restore_to_groups(GroupEntry::List(list), memberships);
+            }
+            Deleted::List {
+                name,
+                items,
+                icon,
+                settings,
+                max,
+                order,
+                memberships,
+            } => {
+                if self.state.lists.contains_key(&name) {
+                    self.toast(format!("There is already a list called {}", name));
+                    return;
+                }
+                self.state.lists.insert(name.clone(), items);
+                if let Some(icon) = icon {
+                    self.state.list_icons.insert(name.clone(), icon);
+                }
+                if let Some(settings) = settings {
+                    self.state.list_settings.insert(name.clone(), settings);
+                }
+                if let Some(max) = max {
+                    self.state.list_max.insert(name.clone(), max);
+                }
+                if let Some(order) = order {
+                    let order = order.min(self.state.list_order.len());
+                    self.state.list_order.insert(order, name.clone());
+                }
+                self.restore_to_groups(GroupEntry::List(name), memberships);
+            }
+            Deleted::Group {
+                name,
+                entries,
+                background,
+                settings,
+                order,
+                memberships,
+            } => {
+                if self.state.groups.contains_key(&name) {
+                    self.toast(format!("There is already a group called {}", name));
+                    return;
+                }
+                self.state.groups.insert(name.clone(), entries);
+                if let Some(background) = background {
+                    self.state
+                        .group_backgrounds
+                        .insert(name.clone(), background);
+                }
+                if let Some(settings) = settings {
+                    self.state.group_settings.insert(name.clone(), settings);
+                }
+                if let Some(order) = order {
+                    let order = order.min(self.state.group_order.len());
+                    self.state.group_order.insert(order, name.clone());
+                }
+                self.restore_to_groups(GroupEntry::Group { group: name }, memberships);
+            }
+            Deleted::GroupEntry {
+                group,
+                entry,
+                positions,
+            } => {
+                let memberships = positions
+                    .into_iter()
+                    .map(|idx| (group.clone(), idx))
+                    .collect();
+                self.restore_to_groups(entry, memberships);
             }
         }
     }
-    fn render_group_element(&self, name: &str) -> Html {
-        let name2 = name.to_owned();
-        match self.view.cache.get(name) {
-            Some(item) => html! {
-                <>
-                <dt>{name}</dt>
-                <dd>{item.render_chosen()}
-                <button class="delete" onclick=self.link.callback(move |_| Msg::ThawList(name2.clone()))>
-                    {"Unlock"}
-                </button>
-                </dd>
-                </>
-            },
-            None => {
-                let item = self.choose_from_list(&name);
-                html! {
-                    <>
-                    <dt>{name}</dt>
-                    <dd
-                        onclick=self.link.callback(move |_| Msg::FreezeList(name2.clone()) )
-                    >{item.render_flash()}
-                    </dd>
-                    </>
+    fn check_link(&mut self) {
+        let link = self
+            .get_current_index_and_item()
+            .and_then(|(_, item)| item.link.clone());
+        match link {
+            Some(link) if Url::new(&link).is_err() => {
+                self.view.item_problems.insert(
+                    "link",
+                    "This isn't a complete link, e.g. https://example.com/".to_owned(),
+                );
+            }
+            _ => {
+                self.view.item_problems.remove("link");
+            }
+        }
+    }
+    // Starts loading the current item's image; the answer comes back as
+    // Msg::ImageChecked
+    fn check_image(&mut self) {
+        self.image_check = None;
+        self.view.item_problems.remove("image");
+        let url = match self
+            .get_current_index_and_item()
+            .and_then(|(_, item)| item.image.clone())
+        {
+            Some(url) => url,
+            None => return,
+        };
+        let image = match HtmlImageElement::new() {
+            Ok(image) => image,
+            Err(_) => return,
+        };
+        let checked = |loaded: bool| {
+            let callback = self.link.callback(|msg: Msg| msg);
+            let url = url.clone();
+            Closure::wrap(Box::new(move || {
+                callback.emit(Msg::ImageChecked {
+                    url: url.clone(),
+                    loaded,
+                })
+            }) as Box<dyn FnMut()>)
+        };
+        let on_load = checked(true);
+        let on_error = checked(false);
+        image.set_onload(Some(on_load.as_ref().unchecked_ref()));
+        image.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        image.set_src(&url);
+        self.image_check = Some(ImageCheck {
+            image,
+            _on_load: on_load,
+            _on_error: on_error,
+        });
+    }
+    fn fetch_link_details(&mut self) {
+        let list = self.view.current_list.clone();
+        let (idx, link) = match self.get_current_index_and_item() {
+            Some((idx, item)) if item.name.is_none() || item.image.is_none() => {
+                match item.link.clone() {
+                    Some(link) => (idx, link),
+                    None => return,
                 }
             }
+            _ => return,
+        };
+        let request = match Request::get(link.as_str()).body(Nothing) {
+            Ok(request) => request,
+            Err(_) => return,
+        };
+        let link2 = link.clone();
+        let callback = self.link.callback(move |response: Response<Text>| {
+            let (meta, body) = response.into_parts();
+            Msg::LinkFetched {
+                list: list.clone(),
+                idx,
+                link: link2.clone(),
+                page: body.ok().filter(|_| meta.status.is_success()),
+            }
+        });
+        match self.fetch.fetch(request, callback) {
+            Ok(task) => self._fetch_link = Some(task),
+            Err(_) => self.toast(format!("Couldn't fetch {}.", link)),
         }
     }
-    fn render_list_name(&self, name: &str) -> Html {
-        let name3 = name.to_owned();
-        let (class, callback) = if name == self.view.current_list {
-            ("selected", self.link.callback(move |_| Msg::BlurList))
-        } else {
-            (
-                "",
-                self.link.callback(move |_| Msg::FocusList(name3.clone())),
-            )
+    fn render_busy(&self) -> Html {
+        if !self.view.busy {
+            return html! {};
+        }
+        html! {
+            <div class="overlay busy">
+                <div class="spinner"></div>
+            </div>
+        }
+    }
+    fn render_spin_settings(&self) -> Html {
+        let easing = self.state.spin.easing;
+        let option = |value: &str, label: &str, this: Easing| {
+            html! {
+                <option value=value selected=easing == this>{label}</option>
+            }
         };
-        let buttons = if self.view.current_group != "" {
-            let name1 = name.to_owned();
-            let name2 = name.to_owned();
+        html! {
+            <span class="spin">
+                <input class="edit" type="number" min="0" step="any" placeholder="Spin seconds (blank spins until clicked)"
+                    value=match self.state.spin.duration_ms {
+                        ms if ms > 0.0 => (ms / 1000.0).to_string(),
+                        _ => "".to_owned(),
+                    }
+                    oninput=self.link.callback(|e: InputData| Msg::EditSpinDuration(e.value))
+                />
+                <select onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Select(select) => match select.value().as_str() {
+                        "linear" => Msg::SetSpinEasing(Easing::Linear),
+                        "bounce" => Msg::SetSpinEasing(Easing::Bounce),
+                        _ => Msg::SetSpinEasing(Easing::EaseOut),
+                    },
+                    _ => Msg::Nothing,
+                })>
+                    {option("linear", "Slow down steadily", Easing::Linear)}
+                    {option("ease_out", "Slow down quickly, then gently", Easing::EaseOut)}
+                    {option("bounce", "Bounce to a stop", Easing::Bounce)}
+                </select>
+            </span>
+        }
+    }
+    fn render_toast(&self) -> Html {
+        match self.view.toast.as_ref() {
+            Some((message, _)) if self.view.undo_delete.is_some() => html! {
+                <div class="toast">
+                    {message}
+                    <button onclick=self.link.callback(|_| Msg::UndoDelete)>{"Undo"}</button>
+                </div>
+            },
+            Some((message, _)) => html! {<div class="toast">{message}</div>},
+            None => html! {},
+        }
+    }
+    fn render_export_link(&self) -> Html {
+        if let Some(url) = self.view.export_url.as_ref().cloned() {
+            let (count, bytes) = self.large_images().fold((0, 0), |(count, bytes), image| {
+                (count + 1, bytes + image.len())
+            });
             html! {
                 <>
-                <button class="add" onclick=self.link.callback(move |_| Msg::AddToGroup(name1.clone()))>
-                    {"+"}
-                </button>
-                <button class="remove" onclick=self.link.callback(move |_| Msg::RemoveGroupItem(name2.clone()))>
-                    {"-"}
-                </button>
+                <a href=url target="_blank">{"Export Data"}</a>
+                {if count > 0 {
+                    html! {
+                        <span class="export-images">
+                            <span class="warning">
+                                {format!("{} embedded images add {:.1} MB to the export.", count, bytes as f64 / 1_000_000.0)}
+                            </span>
+                            <label>
+                                <input type="checkbox" checked=self.view.export_without_images
+                                    onclick=self.link.callback(|_| Msg::ToggleExportImages)
+                                />
+                                {"Leave them out"}
+                            </label>
+                        </span>
+                    }
+                } else {
+                    html! {}
+                }}
                 </>
             }
         } else {
-            html! {<></>}
+            html! {
+                <p>{"Error generating data export"}</p>
+            }
+        }
+    }
+    // A page that shows `board`, the markup render_print made, and needs
+    // nothing else to open
+    fn snapshot_html(&self, board: &str) -> String {
+        let title = match self.boards.current.as_str() {
+            "" => "Automatic Spoon!".to_owned(),
+            board => format!("Automatic Spoon! {}", board),
         };
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+{style}</style>
+</head>
+<body>
+<h1>{title}</h1>
+{board}
+</body>
+</html>
+"#,
+            title = escape_html(&title),
+            style = STYLESHEET,
+            board = board
+        )
+    }
+    // Save `contents` as a file named after the board, e.g.
+    // automatic-spoon-Work.md
+    fn download(&mut self, contents: &str, mime: &str, extension: &str, what: &str) {
+        let strings = Array::new();
+        strings.push(&contents.into());
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(mime);
+        let url = match Blob::new_with_str_sequence_and_options(&strings.into(), &options)
+            .and_then(|blob| Url::create_object_url_with_blob(&blob))
+        {
+            Ok(url) => url,
+            Err(_) => return self.dialog.alert(&format!("Couldn't create the {}.", what)),
+        };
+        let name = match self.boards.current.as_str() {
+            "" => format!("automatic-spoon.{}", extension),
+            board => format!("automatic-spoon-{}.{}", board, extension),
+        };
+        let link = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|d| d.create_element("a").ok())
+            .and_then(|a| a.dyn_into::<HtmlElement>().ok());
+        if let Some(link) = link {
+            let _ = link.set_attribute("href", &url);
+            let _ = link.set_attribute("download", &name);
+            link.click();
+        }
+        // The download has its own hold on the file by now
+        let _ = Url::revoke_object_url(&url);
+    }
+    // The history summed up as Markdown: how often each item came up against
+    // the odds its weight gives it now
+    fn roll_report(&self) -> String {
+        let history = &self.state.history;
+        let date = |time: f64| {
+            String::from(
+                js_sys::Date::new(&time.into()).to_locale_string("default", &JsValue::UNDEFINED),
+            )
+        };
+        let mut report = String::from("# Roll report\n\n");
+        if !self.boards.current.is_empty() {
+            report += &format!("Board: {}\n\n", self.boards.current);
+        }
+        report += &format!("Total draws: {}\n\n", history.len());
+        if let (Some(first), Some(last)) = (history.first(), history.last()) {
+            report += &format!("From {} to {}\n\n", date(first.time), date(last.time));
+        }
+        let mut counts: BTreeMap<&str, Vec<(String, usize)>> = BTreeMap::new();
+        for draw in history {
+            let label = item_label(&draw.item);
+            let items = counts.entry(&draw.list).or_default();
+            match items.iter_mut().find(|(name, _)| *name == label) {
+                Some((_, count)) => *count += 1,
+                None => items.push((label, 1)),
+            }
+        }
+        for (list, mut items) in counts {
+            let total: usize = items.iter().map(|(_, count)| count).sum();
+            // What each item's weight asks for, among the enabled items
+            let mut intended: Vec<(String, f64)> = self
+                .state
+                .lists
+                .get(list)
+                .into_iter()
+                .flatten()
+                .filter(|item| item.enabled)
+                .map(|item| (item_label(&item.name), self.item_weight(list, item)))
+                .collect();
+            let weight_total: f64 = intended.iter().map(|(_, weight)| weight).sum();
+            for (_, weight) in intended.iter_mut() {
+                *weight = match weight_total > 0.0 {
+                    true => *weight / weight_total,
+                    false => 0.0,
+                };
+            }
+            // Items that could have come up but never did count too
+            for (name, _) in intended.iter() {
+                if !items.iter().any(|(drawn, _)| drawn == name) {
+                    items.push((name.clone(), 0));
+                }
+            }
+            items.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+            report += &format!("## {} ({} draws)\n\n", markdown_cell(list), total);
+            if let (Some((most, most_count)), Some((least, least_count))) =
+                (items.first(), items.last())
+            {
+                report += &format!(
+                    "Most chosen: {} ({})  \nLeast chosen: {} ({})\n\n",
+                    most, most_count, least, least_count
+                );
+            }
+            report += "| Item | Draws | Observed | Intended |\n|---|---:|---:|---:|\n";
+            let percent = |share: f64| format!("{:.1}%", share * 100.0);
+            for (name, count) in items.iter() {
+                let expected = intended
+                    .iter()
+                    .find(|(intended, _)| intended == name)
+                    .map(|(_, share)| percent(*share))
+                    .unwrap_or_else(|| "not in list".to_owned());
+                report += &format!(
+                    "| {} | {} | {} | {} |\n",
+                    markdown_cell(name),
+                    count,
+                    percent(*count as f64 / total as f64),
+                    expected
+                );
+            }
+            report += "\n";
+        }
+        report
+    }
+    // Every group with the picks currently frozen in it, without controls
+    fn render_print(&self) -> Html {
         html! {
-            <li
-                class=class
-                onclick=callback
-            >
-                {buttons}
-                {name}
-            </li>
+            <div class="print">
+            {for ordered(&self.state.group_order, &self.state.groups).into_iter().map(|group| html! {
+                <div class="print-group">
+                    <h2>{group}</h2>
+                    <dl>
+                    {for self.group_lists(group).iter().map(|list| html! {
+                        <>
+                        <dt>{self.list_label(list)}</dt>
+                        <dd>
+                            {match self.view.cache.get(list) {
+                                Some(chosen) => chosen.item.render_chosen(self.template(list)),
+                                None => html! {<p class="not-picked">{"Not picked"}</p>},
+                            }}
+                        </dd>
+                        </>
+                    })}
+                    </dl>
+                </div>
+            })}
+            </div>
         }
     }
-    fn render_lists(&self) -> Html {
+    fn render_snapshot(&self) -> Html {
+        if !self.view.exporting_html {
+            return html! {};
+        }
         html! {
-            <div  class="lists">
-            <p>{"Lists"}</p>
-            <ul>
-                {
-                    for self.state.lists.keys().map(|name| {self.render_list_name(name)})
-                }
-                <li>
-                    <input class="edit"
-                        type="text"
-                        placeholder="New List"
-                        value=&self.view.new_list_name
-                        oninput=self.link.callback(move |e: InputData| Msg::UpdateListName(e.value))
-                        onkeypress=self.link.callback(move |e: KeyboardEvent| {
-                            if e.key() == "Enter" { Msg::CreateList } else { Msg::Nothing }
-                    }) />
-                </li>
-            </ul>
+            <div style="display: none" ref=self.snapshot_panel.clone()>
+                {self.render_print()}
             </div>
         }
     }
-    fn render_list_entry(&self, idx: usize, item: &Item) -> Html {
-        let name = item
-            .name
+    // Just the group's picks and a way to draw them, with nothing to edit
+    fn render_embed(&self, group: &str) -> Html {
+        if !self.state.groups.contains_key(group) {
+            let message = match self._fetch_board.is_some() || self.view.busy {
+                true => "Loading\u{2026}".to_owned(),
+                false => format!("There is no group called {}", group),
+            };
+            return html! {
+                <div class="embed"><p class="missing">{message}</p></div>
+            };
+        }
+        let background = self.state.group_backgrounds.get(group);
+        html! {
+            <div class="embed group"
+                style=background.map(|bg| background_style(bg)).unwrap_or_default()
+            >
+                <p>{group}</p>
+                <button onclick=self.link.callback(|_| Msg::ThawAllLists)>
+                    {"Roll Again"}
+                </button>
+                <dl>
+                    {for self.group_lists(group).iter().map(|name| self.render_embed_list(name))}
+                </dl>
+            </div>
+        }
+    }
+    fn render_embed_list(&self, name: &str) -> Html {
+        let item = match self.view.cache.get(name) {
+            Some(chosen) => chosen.item.render_chosen(self.template(name)),
+            None => {
+                let name = name.to_owned();
+                let flash = self.flash_item(&name);
+                html! {
+                    <div onclick=self.link.callback(move |_| Msg::FreezeList(name.clone()))>
+                        {flash.render_flash()}
+                    </div>
+                }
+            }
+        };
+        html! {
+            <>
+            <dt>{self.list_label(name)}</dt>
+            <dd>{item}</dd>
+            </>
+        }
+    }
+    // Tell the embedding page how tall the board is, whenever that changes,
+    // as `{type: "automatic-spoon:resize", height}`
+    fn post_embed_height(&mut self) {
+        let window = match web_sys::window() {
+            Some(window) => window,
+            None => return,
+        };
+        let height = match window.document().and_then(|d| d.document_element()) {
+            Some(root) => root.scroll_height(),
+            None => return,
+        };
+        if height == self.view.embed_height {
+            return;
+        }
+        self.view.embed_height = height;
+        if let Ok(Some(parent)) = window.parent() {
+            let message = js_sys::Object::new();
+            let _ =
+                js_sys::Reflect::set(&message, &"type".into(), &"automatic-spoon:resize".into());
+            let _ = js_sys::Reflect::set(&message, &"height".into(), &height.into());
+            let _ = parent.post_message(&message, "*");
+        }
+    }
+    fn render_fullscreen(&self) -> Html {
+        let (name, item) = match self
+            .view
+            .fullscreen
             .as_ref()
-            .cloned()
-            .unwrap_or_else(|| format!("{}", idx));
-        let idx2 = idx;
-        let (class, callback) = if Some(idx) == self.view.current_item {
-            ("selected", self.link.callback(|_| Msg::BlurItem))
-        } else {
-            ("", self.link.callback(move |_| Msg::FocusItem(idx2)))
+            .and_then(|name| Some((name, &self.view.cache.get(name)?.item)))
+        {
+            Some(found) => found,
+            None => return html! {},
         };
         html! {
-            <li class=class
-                onclick=callback
+            <div class="fullscreen" ref=self.fullscreen_panel.clone()
+                ondoubleclick=self.link.callback(|_| Msg::CloseOverlay)
             >
-                <button onclick=self.link.callback(move |_| Msg::RemoveListItem(idx))>
-                    {"-"}
-                </button>
-                {name}
-            </li>
+                {item.render_chosen(self.template(name))}
+            </div>
         }
     }
-    fn render_list(&self) -> Html {
-        if let Some(list) = self.state.lists.get(&self.view.current_list) {
-            let name = self.view.current_list.to_owned();
+    // Only shown on narrow screens, where one part of the board fits at a time
+    fn render_tabs(&self) -> Html {
+        let tab = |(tab, label): &(MobileTab, &str)| {
+            let tab = *tab;
+            let class = match tab == self.view.mobile_tab {
+                true => "selected",
+                false => "",
+            };
             html! {
-                <div class="list">
-                <p>{&name}</p>
-                <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveList(name.clone()))>
-                    {"Delete List"}
+                <button class=class onclick=self.link.callback(move |_| Msg::ShowTab(tab))>
+                    {label}
                 </button>
-                <ul class="entries">
-                    {for list.iter().enumerate().map(|(idx, item)| self.render_list_entry(idx, &item))}
-                    <li>
-                        <button onclick=self.link.callback(move |_| Msg::CreateItem)>
-                            {"+"}
-                        </button>
-                    </li>
-                </ul>
-                </div>
-            }
-        } else {
-            html! {
-                <div class="list"></div>
             }
+        };
+        html! {
+            <div class="tabs">
+                {for MOBILE_TABS.iter().map(tab)}
+            </div>
         }
     }
-    fn render_edit_item(&self) -> Html {
-        if let Some((_idx, item)) = self.get_current_index_and_item() {
-            html! {
-                <div class="edit-item">
-                {item.render_edit(&self.link)}
-                </div>
-            }
-        } else {
-            html! {
+    fn render_sample_offer(&self) -> Html {
+        if !self.view.offer_sample || !self.state.lists.is_empty() || !self.state.groups.is_empty()
+        {
+            return html! {};
+        }
+        html! {
+            <div class="sample-offer">
+                {"New here? Try an example board with a couple of lists and a group that draws from both."}
+                <button onclick=self.link.callback(|_| Msg::LoadSampleData)>{"Load Example"}</button>
+                <button onclick=self.link.callback(|_| Msg::DismissSample)>{"No Thanks"}</button>
+            </div>
+        }
+    }
+    fn render_group_picks(&self) -> Html {
+        let picks = match self.view.group_picks.as_ref() {
+            Some(picks) => picks,
+            None => return html! {},
+        };
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                <div class="group-picks" onclick=self.link.callback(|e: MouseEvent| {
+                    e.stop_propagation();
+                    Msg::Nothing
+                })>
+                    <p>{"Copy these picks to share them"}</p>
+                    <textarea class="edit" readonly=true rows=picks.lines().count().max(1) value=picks/>
+                    <button onclick=self.link.callback(|_| Msg::CloseOverlay)>{"Done"}</button>
+                </div>
+            </div>
+        }
+    }
+    fn render_settings(&self) -> Html {
+        if !self.view.show_settings {
+            return html! {};
+        }
+        let drawing = html! {
+            <fieldset>
+                <legend>{"Drawing"}</legend>
+                {self.render_spin_settings()}
+                <label>
+                    <input type="checkbox" checked=self.state.fair_draws
+                        onclick=self.link.callback(|_| Msg::ToggleFairDraws)
+                    />
+                    {"Record the seed of each draw so it can be verified"}
+                </label>
+                <label>
+                    <input type="checkbox" checked=self.state.autofreeze
+                        onclick=self.link.callback(|_| Msg::ToggleAutofreeze)
+                    />
+                    {"Freeze every group as soon as the board opens"}
+                </label>
+            </fieldset>
+        };
+        let editing = html! {
+            <fieldset>
+                <legend>{"Editing"}</legend>
+                <label>
+                    <input type="checkbox" checked=self.state.item_editor_dialog
+                        onclick=self.link.callback(|_| Msg::ToggleItemEditorDialog)
+                    />
+                    {"Edit items in a dialog instead of the side pane"}
+                </label>
+                <label>
+                    <input type="checkbox" checked=self.state.fetch_link_details
+                        onclick=self.link.callback(|_| Msg::ToggleFetchLinkDetails)
+                    />
+                    {"Fill in names and images by fetching new links (contacts the linked site)"}
+                </label>
+            </fieldset>
+        };
+        let privacy = html! {
+            <fieldset>
+                <legend>{"History"}</legend>
+                <label class="history-ttl">
+                    {"Forget draws after "}
+                    <input type="number" min="0" placeholder="0"
+                        title="0 keeps them"
+                        value=match self.state.history_ttl_days {
+                            0 => String::new(),
+                            days => days.to_string(),
+                        }
+                        onchange=self.link.callback(|e: ChangeData| match e {
+                            ChangeData::Value(text) => Msg::EditHistoryTtl(text),
+                            _ => Msg::Nothing,
+                        })
+                    />
+                    {" days"}
+                </label>
+            </fieldset>
+        };
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                <div class="settings" role="dialog" aria-label="Settings"
+                    onclick=self.link.callback(|e: MouseEvent| {
+                        e.stop_propagation();
+                        Msg::Nothing
+                    })
+                >
+                    <p>{"Settings"}</p>
+                    {drawing}
+                    {editing}
+                    {privacy}
+                    <button onclick=self.link.callback(|_| Msg::ResetSettings)>
+                        {"Reset to Defaults"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                        {"Done"}
+                    </button>
+                </div>
+            </div>
+        }
+    }
+    fn render_help(&self) -> Html {
+        if !self.view.show_help {
+            return html! {};
+        }
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                <div class="help" onclick=self.link.callback(|e: MouseEvent| {
+                    e.stop_propagation();
+                    Msg::Nothing
+                })>
+                    <p>{"Keyboard Shortcuts"}</p>
+                    <dl>
+                    {for SHORTCUTS.iter().map(|shortcut| html! {
+                        <>
+                        <dt><kbd>{if shortcut.alt { format!("Alt+{}", shortcut.key) } else { shortcut.key.to_owned() }}</kbd></dt>
+                        <dd>{shortcut.description}</dd>
+                        </>
+                    })}
+                    </dl>
+                </div>
+            </div>
+        }
+    }
+    fn render_search(&self) -> Html {
+        // The alias is set when it matched better than the name
+        let mut results: Vec<(fuzzy::Match, &str, usize, String, Option<&str>)> = vec![];
+        if !self.view.search.trim().is_empty() {
+            for (list, items) in self.state.lists.iter() {
+                for (idx, item) in items.iter().enumerate() {
+                    let text = item
+                        .name
+                        .as_ref()
+                        .or(item.link.as_ref())
+                        .cloned()
+                        .unwrap_or_else(|| format!("{}", idx));
+                    if let Some((found, alias)) =
+                        fuzzy::score_aliased(&self.view.search, &text, &item.aliases)
+                    {
+                        results.push((found, list, idx, text, alias));
+                    }
+                }
+            }
+        }
+        results.sort_by_key(|result| std::cmp::Reverse(result.0.score));
+        let total = results.len();
+        html! {
+            <div class="search">
+                <input class="edit" type="search" placeholder="Search items"
+                    value=&self.view.search
+                    oninput=self.link.callback(|e: InputData| Msg::SearchItems(e.value))
+                />
+                <ul>
+                {for results.into_iter().take(SEARCH_LIMIT).map(|(found, list, idx, text, alias)| {
+                    let list_name = list.to_owned();
+                    let label = match alias {
+                        Some(alias) => html! {
+                            <>
+                            {text}
+                            <span class="alias">{"aka "}{render_highlighted(alias, &found.positions)}</span>
+                            </>
+                        },
+                        None => render_highlighted(&text, &found.positions),
+                    };
+                    html! {
+                        <li onclick=self.link.callback(move |_| Msg::OpenSearchResult(list_name.clone(), idx))>
+                            {label}
+                            <span class="list-name">{self.list_label(list)}</span>
+                        </li>
+                    }
+                })}
+                {if total > SEARCH_LIMIT {
+                    html! {<li>{format!("and {} more", total - SEARCH_LIMIT)}</li>}
+                } else {
+                    html! {}
+                }}
+                </ul>
+            </div>
+        }
+    }
+    fn render_breadcrumb(&self) -> Html {
+        let has_group = self.state.groups.contains_key(&self.view.current_group);
+        let has_list = self.state.lists.contains_key(&self.view.current_list);
+        let item = self.get_current_index_and_item();
+        // Clicking a segment clears everything below it, or the segment
+        // itself when it is the deepest one.
+        let mut segments: Vec<(String, Callback<MouseEvent>)> = vec![];
+        if has_group {
+            let callback = if has_list {
+                self.link.callback(|_| Msg::BlurList)
+            } else {
+                self.link.callback(|_| Msg::BlurGroup)
+            };
+            segments.push((self.view.current_group.clone(), callback));
+        }
+        if has_list {
+            let callback = if item.is_some() {
+                self.link.callback(|_| Msg::BlurItem)
+            } else {
+                self.link.callback(|_| Msg::BlurList)
+            };
+            segments.push((self.list_label(&self.view.current_list), callback));
+        }
+        if let Some((idx, item)) = item {
+            let name = item.name.clone().unwrap_or_else(|| format!("{}", idx));
+            segments.push((name, self.link.callback(|_| Msg::BlurItem)));
+        }
+        let count = segments.len();
+        html! {
+            <div class="breadcrumb">
+            {for segments.into_iter().enumerate().map(|(idx, (label, callback))| html! {
+                <>
+                <span class="crumb" onclick=callback>{label}</span>
+                {if idx + 1 < count { html! {<span class="separator">{" > "}</span>} } else { html! {} }}
+                </>
+            })}
+            </div>
+        }
+    }
+    fn render_boards(&self) -> Html {
+        let option = |name: &str, label: &str| {
+            html! {
+                <option value=name selected=name == self.boards.current>{label}</option>
+            }
+        };
+        html! {
+            <div class="boards">
+                <select onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SwitchBoard(select.value()),
+                    _ => Msg::Nothing,
+                })>
+                    {option("", "Default Board")}
+                    {for self.boards.names.iter().map(|name| option(name, name))}
+                </select>
+                <input class="edit"
+                    type="text"
+                    placeholder="New Board"
+                    value=&self.view.new_board_name
+                    oninput=self.link.callback(|e: InputData| Msg::UpdateBoardName(e.value))
+                    onkeypress=self.link.callback(|e: KeyboardEvent| {
+                        if e.key() == "Enter" { Msg::CreateBoard } else { Msg::Nothing }
+                }) />
+            </div>
+        }
+    }
+    fn render_trash(&self) -> Html {
+        if self.state.trashed.is_empty() {
+            return html! {};
+        }
+        html! {
+            <details class="trash">
+                <summary>{format!("Trash ({})", self.state.trashed.len())}</summary>
+                <ul>
+                {for self.state.trashed.iter().enumerate().rev().map(|(idx, (list, item))| html! {
+                    <li>
+                        <button onclick=self.link.callback(move |_| Msg::RestoreTrashed(idx))>
+                            {"Restore"}
+                        </button>
+                        <button class="delete" onclick=self.link.callback(move |_| Msg::DeleteTrashed(idx))>
+                            {"Delete Forever"}
+                        </button>
+                        {item_label(&item.name)}
+                        <span class="list-name">{self.list_label(list)}</span>
+                    </li>
+                })}
+                </ul>
+            </details>
+        }
+    }
+    fn render_history(&self) -> Html {
+        if self.state.history.is_empty() {
+            return html! {};
+        }
+        // Tick re-renders often enough to keep the relative times current
+        let now = js_sys::Date::now();
+        html! {
+            <details class="history">
+                <summary>{format!("History ({})", self.state.history.len())}</summary>
+                <button onclick=self.link.callback(|_| Msg::ExportReport)>
+                    {"Download Report"}
+                </button>
+                <ul>
+                {for self.state.history.iter().enumerate().rev().map(|(idx, draw)| html! {
+                    <li>
+                        <span class="time">
+                            {String::from(js_sys::Date::new(&draw.time.into()).to_locale_string("default", &JsValue::UNDEFINED))}
+                        </span>
+                        <span class="ago">{format_relative(now - draw.time)}</span>
+                        {item_label(&draw.item)}
+                        <span class="list-name">{self.list_label(&draw.list)}</span>
+                        {match draw.proof.as_ref() {
+                            Some(proof) => html! {
+                                <>
+                                <span class="proof">
+                                    {format!("seed {}, {} draw from {} items", proof.seed, proof.algorithm, proof.candidates.len())}
+                                </span>
+                                <button onclick=self.link.callback(move |_| Msg::VerifyDraw(idx))>
+                                    {"Verify"}
+                                </button>
+                                </>
+                            },
+                            None => html! {},
+                        }}
+                    </li>
+                })}
+                </ul>
+            </details>
+        }
+    }
+    fn render_json_import(&self) -> Html {
+        let data = self.view.json_import.clone();
+        html! {
+            <div class="json-import">
+                <textarea class="edit" placeholder="Exported data"
+                    value=&self.view.json_import
+                    oninput=self.link.callback(move |e: InputData| Msg::UpdateJsonImport(e.value))
+                />
+                <button onclick=self.link.callback(move |_| Msg::ImportJson(data.clone()))>
+                    {"Import Data"}
+                </button>
+            </div>
+        }
+    }
+    fn render_bookmark_import(&self) -> Html {
+        let folders = match self.view.bookmark_import.as_ref() {
+            Some(folders) => folders,
+            None => {
+                return html! {
+                    <label class="bookmark-import">
+                        {"Import bookmarks.html "}
+                        <input type="file" accept=".html,.htm,text/html"
+                            onchange=self.link.callback(|e: ChangeData| match e {
+                                ChangeData::Files(files) => match files.get(0) {
+                                    Some(file) => Msg::ReadBookmarks(file),
+                                    None => Msg::Nothing,
+                                },
+                                _ => Msg::Nothing,
+                            })
+                        />
+                    </label>
+                }
+            }
+        };
+        let folder = |(idx, (folder, list)): (usize, &(bookmarks::Folder, String))| {
+            html! {
+                <li>
+                    {format!("{} ({}) \u{2192} ", folder.name, folder.bookmarks.len())}
+                    <input type="text" placeholder="Skip" value=list
+                        oninput=self.link.callback(move |e: InputData| Msg::SetBookmarkList {
+                            folder: idx,
+                            list: e.value,
+                        })
+                    />
+                </li>
+            }
+        };
+        html! {
+            <div class="bookmark-import">
+                <p>{"Choose a list for each folder, or leave it blank to skip it"}</p>
+                <ul>
+                    {for folders.iter().enumerate().map(folder)}
+                </ul>
+                <button onclick=self.link.callback(|_| Msg::ImportBookmarks)>{"Import Bookmarks"}</button>
+                <button onclick=self.link.callback(|_| Msg::CancelBookmarks)>{"Cancel"}</button>
+            </div>
+        }
+    }
+    fn render_outline_import(&self) -> Html {
+        let outline = self.view.outline.clone();
+        html! {
+            <div class="outline">
+                <textarea class="edit" placeholder="Group\n    List\n        Item"
+                    value=&self.view.outline
+                    oninput=self.link.callback(move |e: InputData| Msg::UpdateOutline(e.value))
+                />
+                <button onclick=self.link.callback(move |_| Msg::ImportOutline(outline.clone()))>
+                    {"Import Outline"}
+                </button>
+            </div>
+        }
+    }
+    fn render_groups(&self) -> Html {
+        html! {
+            <div class="groups">
+            <p>{"Groups"}</p>
+            <ul>
+                {
+                    for ordered(&self.state.group_order, &self.state.groups)
+                        .into_iter()
+                        .map(|group| self.render_group_name(group))
+                }
+                <li>
+                    <input class="edit"
+                        type="text"
+                        placeholder="New Group"
+                        value=&self.view.new_group_name
+                        oninput=self.link.callback(move |e: InputData| Msg::UpdateGroupName(e.value))
+                        onkeypress=self.link.callback(move |e: KeyboardEvent| {
+                            if e.key() == "Enter" { Msg::CreateGroup } else { Msg::Nothing }
+                    }) />
+                </li>
+            </ul>
+            </div>
+        }
+    }
+    fn render_group_name(&self, group: &str) -> Html {
+        let name = group.to_owned();
+        let name2 = name.clone();
+        let (class, callback) = if name == self.view.current_group {
+            ("selected", self.link.callback(move |_| Msg::BlurGroup))
+        } else {
+            (
+                "",
+                self.link.callback(move |_| Msg::FocusGroup(name.clone())),
+            )
+        };
+        let icons: String = self
+            .group_lists(group)
+            .iter()
+            .filter_map(|list| self.state.list_icons.get(list))
+            .map(String::as_str)
+            .collect();
+        let buttons = if !self.view.current_group.is_empty() && group != self.view.current_group {
+            let entry = GroupEntry::Group {
+                group: group.to_owned(),
+            };
+            let entry2 = entry.clone();
+            html! {
+                <>
+                <button class="add" onclick=self.link.callback(move |_| Msg::AddToGroup(entry.clone()))>
+                    {"+"}
+                </button>
+                <button class="remove" onclick=self.link.callback(move |_| Msg::RemoveGroupItem(entry2.clone()))>
+                    {"-"}
+                </button>
+                </>
+            }
+        } else {
+            html! {}
+        };
+        let name3 = group.to_owned();
+        let name4 = group.to_owned();
+        html! {
+            <li
+                class=class
+                onclick=callback
+                draggable="true"
+                ondragstart=self.link.callback(move |e: DragEvent| {
+                    if let Some(data) = e.data_transfer() {
+                        let _ = data.set_data("text/plain", &name3);
+                    }
+                    Msg::DragGroup(name3.clone())
+                })
+                ondragover=self.link.callback(|e: DragEvent| {
+                    e.prevent_default();
+                    Msg::Nothing
+                })
+                ondrop=self.link.callback(move |e: DragEvent| {
+                    e.prevent_default();
+                    Msg::DropGroup(name4.clone())
+                })
+            >
+                {buttons}
+                {group}
+                <span class="icons">{icons}</span>
+                <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveGroup(name2.clone()))>
+                    {"Delete"}
+                </button>
+            </li>
+        }
+    }
+    fn render_group(&self) -> Html {
+        if let Some(group) = self.state.groups.get(&self.view.current_group) {
+            let name = self.view.current_group.to_owned();
+            let background = self.state.group_backgrounds.get(&name);
+            let locked = self.board_locked();
+            let share = name.clone();
+            let roll = name.clone();
+            let undo_pick = match self
+                .view
+                .pick_undo
+                .get(&name)
+                .and_then(|stack| stack.last())
+            {
+                Some(undo) => html! {
+                    <button disabled=locked || self.pick_pinned(&undo.list)
+                        onclick=self.link.callback(|_| Msg::UndoPick)>
+                        {format!("Undo Pick ({})", undo.list)}
+                    </button>
+                },
+                None => html! {},
+            };
+            let options = html! {
+                <>
+                <label>
+                    <input type="checkbox" checked=self.celebrates(&name)
+                        onclick=self.link.callback(|_| Msg::ToggleCelebrate)
+                    />
+                    {"Celebrate"}
+                </label>
+                <label>
+                    <input type="checkbox" checked=self.previews_links(&name)
+                        onclick=self.link.callback(|_| Msg::TogglePreviewLinks)
+                    />
+                    {"Open links here"}
+                </label>
+                <label title="Freezing a list unfreezes the others">
+                    <input type="checkbox" checked=self.reveals_exclusively(&name)
+                        onclick=self.link.callback(|_| Msg::ToggleExclusiveReveal)
+                    />
+                    {"One pick at a time"}
+                </label>
+                <input class="value-cap" type="text" inputmode="decimal" placeholder="Value cap"
+                    title="Warn when the picks' values add up to more than this"
+                    value=self.value_cap(&name).map(|cap| cap.to_string()).unwrap_or_default()
+                    onchange=self.link.callback(|e: ChangeData| match e {
+                        ChangeData::Value(text) => Msg::EditValueCap(text),
+                        _ => Msg::Nothing,
+                    })
+                />
+                </>
+            };
+            let class = match js_sys::Date::now() - self.view.group_switched < GROUP_SWITCH_MS {
+                true => "group switching",
+                false => "group",
+            };
+            html! {
+                <div class=class ref=self.group_panel.clone()
+                    style=background.map(|bg| background_style(bg)).unwrap_or_default()
+                >
+                    {self.render_recent()}
+                    <p>{if locked { format!("\u{1f512} {}", name) } else { name.clone() }}</p>
+                    {self.render_group_total(&name)}
+                    <button class=if locked { "selected" } else { "" }
+                        title="Stop picks being drawn, rerolled or unlocked by accident"
+                        onclick=self.link.callback(|_| Msg::ToggleGroupLock)>
+                        {if locked { "Unlock Board" } else { "Lock Board" }}
+                    </button>
+                    <input class="background" type="text" placeholder="Background color or image URL"
+                        value=background.cloned().unwrap_or_default()
+                        oninput=self.link.callback(|e: InputData| Msg::EditGroupBackground(e.value))
+                    />
+                    <button disabled=locked onclick=self.link.callback(move |_| Msg::ThawAllLists)>
+                        {"Unlock All Lists"}
+                    </button>
+                    <button disabled=locked onclick=self.link.callback(move |_| Msg::PreviewGroupRoll(roll.clone()))>
+                        {"Roll Group"}
+                    </button>
+                    <button onclick=self.link.callback(move |_| Msg::ExportGroupPicks(share.clone()))>
+                        {"Share Picks"}
+                    </button>
+                    {undo_pick}
+                    <button class=if self.view.surprise { "selected" } else { "" }
+                        onclick=self.link.callback(|_| Msg::ToggleSurprise)>
+                        {"Surprise Me"}
+                    </button>
+                    {self.render_reveal_controls()}
+                    {self.render_countdown()}
+                    {self.render_group_weights(&name)}
+                    {options}
+                    {if self.current_group_links().is_empty() {
+                        html! {}
+                    } else {
+                        html! {
+                            <button onclick=self.link.callback(move |_| Msg::OpenGroupLinks)>
+                                {"Open All Links"}
+                            </button>
+                        }
+                    }}
+                    {if self.state.groups.values().flatten().any(|entry| self.is_dangling(entry)) {
+                        html! {
+                            <button onclick=self.link.callback(|_| Msg::PruneDanglingRefs)>
+                                {"Remove Missing References"}
+                            </button>
+                        }
+                    } else {
+                        html! {}
+                    }}
+                    <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveGroup(name.clone()))>
+                        {"Delete Group"}
+                    </button>
+                    <dl>
+                        {for group.iter().map(|entry| self.render_group_entry(entry, 1))}
+                    </dl>
+                </div>
+            }
+        } else {
+            html! {
+                <div class="group" ref=self.group_panel.clone()>
+                </div>
+            }
+        }
+    }
+    // The summed value of the picks showing, against the group's cap
+    fn render_group_total(&self, group: &str) -> Html {
+        let cap = self.value_cap(group);
+        let total = match self.group_total(group) {
+            Some(total) => round_value(total),
+            None if cap.is_some() => 0.0,
+            None => return html! {},
+        };
+        let warning = match cap {
+            Some(cap) if total > cap => html! {
+                <span class="warning">{format!("{} over the cap", round_value(total - cap))}</span>
+            },
+            _ => html! {},
+        };
+        let roll = match cap {
+            Some(_) => html! {
+                <button disabled=self.board_locked() || self.reveals_exclusively(group)
+                    title="Reroll the unpinned lists until the total fits the cap"
+                    onclick=self.link.callback(|_| Msg::RollWithinCap)>
+                    {"Roll Within Cap"}
+                </button>
+            },
+            None => html! {},
+        };
+        html! {
+            <p class="total">
+                {match cap {
+                    Some(cap) => format!("Total {} of {}", total, cap),
+                    None => format!("Total {}", total),
+                }}
+                {warning}
+                {roll}
+            </p>
+        }
+    }
+    // The values of the group's frozen picks added up.  None if no pick has a
+    // value.  Picks still hidden by Surprise Me count too, as Roll Within Cap
+    // counts them, and a total doesn't give away which items they are.
+    fn group_total(&self, group: &str) -> Option<f64> {
+        self.group_lists(group)
+            .iter()
+            .filter_map(|list| self.view.cache.get(list)?.item.value)
+            .fold(None, |total, value| Some(total.unwrap_or(0.0) + value))
+    }
+    // Try up to ROLL_UNTIL_LIMIT sets of seeds for the current group's
+    // unpinned lists, then draw the first set whose values fit under `cap`,
+    // or the set that came closest.  Only that set goes into the history.
+    fn roll_within_cap(&mut self, cap: f64) {
+        let group = self.view.current_group.clone();
+        let lists: Vec<String> = self
+            .group_lists(&group)
+            .into_iter()
+            .filter(|list| !self.pick_pinned(list))
+            .collect();
+        let undos: Vec<Option<PickUndo>> =
+            lists.iter().map(|list| self.before_pick(list)).collect();
+        for list in lists.iter() {
+            // Rerolling a consumed pick puts it back first, as Reroll does
+            if self.view.cache.contains_key(list) && self.consumes_on_roll(list) {
+                self.undo_draw(list);
+            }
+            self.refill_bag(list);
+        }
+        let kept: f64 = self
+            .group_lists(&group)
+            .iter()
+            .filter(|list| self.pick_pinned(list))
+            .filter_map(|list| self.view.cache.get(list)?.item.value)
+            .sum();
+        let mut best: Option<(f64, Vec<u64>)> = None;
+        let mut attempts = 0;
+        while attempts < ROLL_UNTIL_LIMIT {
+            attempts += 1;
+            let seeds: Vec<u64> = lists.iter().map(|_| self.rng.borrow_mut().gen()).collect();
+            let total = round_value(
+                kept + lists
+                    .iter()
+                    .zip(seeds.iter())
+                    .filter_map(|(list, seed)| self.seeded_pick(list, *seed)?.value)
+                    .sum::<f64>(),
+            );
+            let closer = best.as_ref().map(|(best, _)| total < *best).unwrap_or(true);
+            if closer {
+                best = Some((total, seeds));
+            }
+            if total <= cap {
+                break;
+            }
+        }
+        let (total, seeds) = match best {
+            Some(best) => best,
+            None => return,
+        };
+        for ((list, seed), undo) in lists.into_iter().zip(seeds).zip(undos) {
+            self.draw_seeded(list, seed, "rolled within cap");
+            self.after_pick(undo);
+        }
+        self.drop_stale_pins();
+        self.toast(match total <= cap {
+            true => format!(
+                "Total {} fits the cap of {} after {} {}",
+                total,
+                cap,
+                attempts,
+                if attempts == 1 { "roll" } else { "rolls" }
+            ),
+            false => format!(
+                "No roll in {} came within the cap of {}; this is the closest, at {}",
+                attempts, cap, total
+            ),
+        });
+    }
+    fn value_cap(&self, group: &str) -> Option<f64> {
+        self.state.group_settings.get(group)?.value_cap
+    }
+    // Per-item weights for the group's lists that apply only here
+    fn render_group_weights(&self, group: &str) -> Html {
+        let overrides = self
+            .state
+            .group_settings
+            .get(group)
+            .map(|settings| &settings.weights);
+        let item = |list: &str, item: &Item| {
+            let name = item.name.clone()?;
+            let weight = overrides
+                .and_then(|weights| weights.get(list)?.get(&name))
+                .map(|weight| weight.to_string())
+                .unwrap_or_default();
+            let list = list.to_owned();
+            Some(html! {
+                <li>
+                    {&name}
+                    <input class="weight" type="text" inputmode="decimal"
+                        placeholder=self.item_weight(&list, item).to_string()
+                        value=weight
+                        onchange=self.link.callback(move |e: ChangeData| match e {
+                            ChangeData::Value(weight) => Msg::EditGroupWeight {
+                                list: list.clone(),
+                                item: name.clone(),
+                                weight,
+                            },
+                            _ => Msg::Nothing,
+                        })
+                    />
+                </li>
+            })
+        };
+        let list = |name: String| {
+            let items = self.state.lists.get(&name)?;
+            Some(html! {
+                <li>
+                    {&name}
+                    <ul>
+                        {for items.iter().filter_map(|entry| item(&name, entry))}
+                    </ul>
+                </li>
+            })
+        };
+        html! {
+            <details class="group-weights">
+                <summary>{"Odds in This Group"}</summary>
+                <ul>
+                    {for self.group_lists(group).into_iter().filter_map(list)}
+                </ul>
+            </details>
+        }
+    }
+    // The last few picks from the current group's lists, newest first
+    fn render_recent(&self) -> Html {
+        let lists = self.group_lists(&self.view.current_group);
+        let recent: Vec<(usize, &Draw)> = self
+            .state
+            .history
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, draw)| lists.contains(&draw.list))
+            .take(RECENT_LIMIT)
+            .collect();
+        if recent.is_empty() {
+            return html! {};
+        }
+        html! {
+            <div class="recent">
+                {for recent.into_iter().map(|(idx, draw)| self.render_recent_pick(idx, draw))}
+            </div>
+        }
+    }
+    fn render_recent_pick(&self, idx: usize, draw: &Draw) -> Html {
+        let image = self
+            .state
+            .lists
+            .get(&draw.list)
+            .and_then(|list| list.iter().find(|item| item.name == draw.item))
+            .and_then(|item| item.image.clone());
+        let label = item_label(&draw.item);
+        let class = match self.board_locked() || self.pick_pinned(&draw.list) {
+            true => "recent-pick disabled",
+            false => "recent-pick",
+        };
+        html! {
+            <span class=class title=format!("{} ({})", label, self.list_label(&draw.list))
+                onclick=self.link.callback(move |_| Msg::ShowRecent(idx))
+            >
+                {match image {
+                    Some(url) => html! { <img src=url alt=label/> },
+                    None => html! { {label} },
+                }}
+            </span>
+        }
+    }
+    fn render_reveal_controls(&self) -> Html {
+        if !self.view.surprise {
+            return html! {};
+        }
+        let order = self.reveal_order(&self.view.current_group);
+        let option = |value: &str, label: &str, this: RevealOrder| {
+            html! {
+                <option value=value selected=order == this>{label}</option>
+            }
+        };
+        html! {
+            <>
+            <button onclick=self.link.callback(|_| Msg::RevealNext)>
+                {"Reveal Next"}
+            </button>
+            <select class="reveal-order" onchange=self.link.callback(|e: ChangeData| match e {
+                ChangeData::Select(select) => match select.value().as_str() {
+                    "reversed" => Msg::SetRevealOrder(RevealOrder::Reversed),
+                    "shuffled" => Msg::SetRevealOrder(RevealOrder::Shuffled),
+                    _ => Msg::SetRevealOrder(RevealOrder::AsListed),
+                },
+                _ => Msg::Nothing,
+            })>
+                {option("as_listed", "In listed order", RevealOrder::AsListed)}
+                {option("reversed", "In reverse order", RevealOrder::Reversed)}
+                {option("shuffled", "In random order", RevealOrder::Shuffled)}
+            </select>
+            <button onclick=self.link.callback(|_| Msg::RevealAll)>
+                {"Reveal All"}
+            </button>
+            </>
+        }
+    }
+    fn board_locked(&self) -> bool {
+        self.view.group_locked.contains(&self.view.current_group)
+    }
+    fn countdown_seconds(&self, group: &str) -> Option<u32> {
+        self.state
+            .group_settings
+            .get(group)?
+            .countdown_seconds
+            .filter(|seconds| *seconds > 0)
+    }
+    // Draw for the countdown's group once its time is up.  Leaving the group
+    // calls the draw off.
+    fn finish_countdown(&mut self) {
+        let now = js_sys::Date::now();
+        match self.view.countdown.as_ref() {
+            Some(countdown) if countdown.left(now) <= 0.0 => {}
+            _ => return,
+        }
+        let countdown = self.view.countdown.take().unwrap();
+        if countdown.group != self.view.current_group {
+            return;
+        }
+        for list in self.group_lists(&countdown.group) {
+            if !self.view.cache.contains_key(&list) {
+                self.update(Msg::FreezeList(list));
+            }
+        }
+    }
+    fn render_countdown(&self) -> Html {
+        let countdown = match self.view.countdown.as_ref() {
+            Some(countdown) if countdown.group == self.view.current_group => countdown,
+            _ => {
+                let seconds = self.countdown_seconds(&self.view.current_group);
+                return html! {
+                    <>
+                    <input class="countdown-seconds" type="number" min="0" placeholder="Seconds"
+                        title="Count down this long, then draw every list"
+                        value=seconds.map(|seconds| seconds.to_string()).unwrap_or_default()
+                        oninput=self.link.callback(|e: InputData| Msg::EditCountdown(e.value))
+                    />
+                    <button disabled=seconds.is_none() || self.board_locked()
+                        onclick=self.link.callback(|_| Msg::StartCountdown)>
+                        {"Start Countdown"}
+                    </button>
+                    </>
+                };
+            }
+        };
+        let left = countdown.left(js_sys::Date::now()).max(0.0);
+        html! {
+            <div class="countdown">
+                <span class="seconds">{(left / 1000.0).ceil()}</span>
+                <button onclick=self.link.callback(|_| Msg::PauseCountdown)>
+                    {if countdown.resumed.is_some() { "Pause" } else { "Resume" }}
+                </button>
+                <button onclick=self.link.callback(|_| Msg::CancelCountdown)>
+                    {"Cancel"}
+                </button>
+            </div>
+        }
+    }
+    fn reveals_exclusively(&self, group: &str) -> bool {
+        self.state
+            .group_settings
+            .get(group)
+            .map(|settings| settings.exclusive_reveal)
+            .unwrap_or_default()
+    }
+    // Once `name` freezes in a one-pick-at-a-time group, unfreeze the
+    // group's other lists.  Pinned picks stay, as they do for Unlock All.
+    // Returns the picks put away, for Undo Pick to bring back.
+    fn thaw_others(&mut self, name: &str) -> Vec<(String, Chosen)> {
+        let group = self.view.current_group.clone();
+        let lists = self.group_lists(&group);
+        if !self.reveals_exclusively(&group) || !lists.iter().any(|list| list == name) {
+            return Vec::new();
+        }
+        let mut thawed = Vec::new();
+        for list in lists {
+            if list != name && !self.pick_pinned(&list) {
+                if let Some(chosen) = self.view.cache.remove(&list) {
+                    thawed.push((list, chosen));
+                }
+            }
+        }
+        self.view.spin_start = 0.0;
+        self.drop_stale_pins();
+        thawed
+    }
+    fn previews_links(&self, group: &str) -> bool {
+        self.state
+            .group_settings
+            .get(group)
+            .map(|settings| settings.preview_links)
+            .unwrap_or_default()
+    }
+    // A frozen pick, whose link opens in the preview pane if the group asks
+    fn render_group_pick(&self, name: &str, chosen: &Chosen) -> Html {
+        let item = chosen.item.render_chosen(self.template(name));
+        let url = match chosen.item.link.clone() {
+            Some(url) if self.previews_links(&self.view.current_group) => url,
+            _ => return item,
+        };
+        html! {
+            <div class="previews" onclick=self.link.callback(move |e: MouseEvent| {
+                // Keep the link from opening its tab as well
+                e.prevent_default();
+                Msg::PreviewLink(url.clone())
+            })>
+                {item}
+            </div>
+        }
+    }
+    // There's no telling from here whether a site refused to be framed:
+    // browsers fire load for the blank or error page shown instead.  So the
+    // pane leads with a new tab, and says why it might be empty.
+    fn render_link_preview(&self) -> Html {
+        let preview = match self.view.link_preview.as_ref() {
+            Some(preview) => preview,
+            None => return html! {},
+        };
+        let status = match preview.loaded {
+            false => html! { <span class="loading">{"Loading\u{2026}"}</span> },
+            true => html! {
+                <span class="hint">{"Blank? Some sites only open in their own tab."}</span>
+            },
+        };
+        html! {
+            <div class="link-preview">
+                <div class="bar">
+                    <a class="open" href=preview.url.as_str() rel="noreferrer noopener"
+                        target="_blank">
+                        {"Open in New Tab"}
+                    </a>
+                    {status}
+                    <button onclick=self.link.callback(|_| Msg::ClosePreview)>{"Close"}</button>
+                </div>
+                <iframe ref=self.preview_frame.clone() src=preview.url.as_str()
+                    sandbox="allow-scripts allow-forms allow-popups"
+                    referrerpolicy="no-referrer"></iframe>
+            </div>
+        }
+    }
+    fn celebrates(&self, group: &str) -> bool {
+        self.state
+            .group_settings
+            .get(group)
+            .map(|settings| settings.celebrate)
+            .unwrap_or_default()
+    }
+    // Throw confetti over `name`'s pick, if the current group celebrates and
+    // the pick is showing
+    fn celebrate(&mut self, name: &str) {
+        if !self.celebrates(&self.view.current_group)
+            || !self.view.cache.contains_key(name)
+            || self.hidden_lists().iter().any(|hidden| hidden == name)
+            || prefers_reduced_motion()
+        {
+            return;
+        }
+        let pieces = {
+            let mut rng = self.rng.borrow_mut();
+            (0..CONFETTI_PIECES)
+                .map(|_| {
+                    let angle = rng.gen_range(0.0, 2.0 * std::f64::consts::PI);
+                    let speed = rng.gen_range(100.0, 350.0);
+                    Confetti {
+                        dx: speed * angle.cos(),
+                        // Mostly upwards, before gravity brings it down
+                        dy: speed * angle.sin() - 250.0,
+                        hue: rng.gen_range(0.0, 360.0),
+                    }
+                })
+                .collect()
+        };
+        self.view.celebration = Some(Celebration {
+            list: name.to_owned(),
+            started: js_sys::Date::now(),
+            pieces,
+        });
+    }
+    fn draw_confetti(&mut self) {
+        let elapsed = match self.view.celebration.as_ref() {
+            Some(celebration) => js_sys::Date::now() - celebration.started,
+            None => return,
+        };
+        let celebration = match self.view.celebration.as_ref() {
+            Some(celebration) if elapsed < CELEBRATE_MS => celebration,
+            _ => {
+                self.view.celebration = None;
+                return;
+            }
+        };
+        let canvas = match self.confetti_canvas.cast::<HtmlCanvasElement>() {
+            Some(canvas) => canvas,
+            None => return,
+        };
+        // Keep the drawing at the size the canvas is shown, so pieces aren't
+        // stretched
+        let (width, height) = (canvas.client_width() as u32, canvas.client_height() as u32);
+        if canvas.width() != width || canvas.height() != height {
+            canvas.set_width(width);
+            canvas.set_height(height);
+        }
+        let context = match canvas
+            .get_context("2d")
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<CanvasRenderingContext2d>().ok())
+        {
+            Some(context) => context,
+            None => return,
+        };
+        let (width, height) = (width as f64, height as f64);
+        let t = elapsed / 1000.0;
+        context.clear_rect(0.0, 0.0, width, height);
+        context.set_global_alpha(1.0 - elapsed / CELEBRATE_MS);
+        for piece in &celebration.pieces {
+            let x = width / 2.0 + piece.dx * t;
+            let y = height / 2.0 + piece.dy * t + CONFETTI_GRAVITY * t * t / 2.0;
+            context.set_fill_style_str(&format!("hsl({}, 90%, 55%)", piece.hue));
+            context.fill_rect(x, y, 6.0, 10.0);
+        }
+    }
+    fn render_confetti(&self, name: &str) -> Html {
+        match self.view.celebration.as_ref() {
+            Some(celebration) if celebration.list == name => html! {
+                <canvas class="confetti" ref=self.confetti_canvas.clone()></canvas>
+            },
+            _ => html! {},
+        }
+    }
+    fn reveal_order(&self, group: &str) -> RevealOrder {
+        self.state
+            .group_settings
+            .get(group)
+            .map(|settings| settings.reveal_order)
+            .unwrap_or_default()
+    }
+    // Frozen lists in the current group whose picks are still face down
+    fn hidden_lists(&self) -> Vec<String> {
+        if !self.view.surprise {
+            return vec![];
+        }
+        self.group_lists(&self.view.current_group)
+            .into_iter()
+            .filter(|name| self.view.cache.contains_key(name))
+            .filter(|name| {
+                !self
+                    .view
+                    .revealed
+                    .contains(&(self.view.current_group.clone(), name.clone()))
+            })
+            .collect()
+    }
+    fn reveal_queue(&self, mut hidden: Vec<String>) -> Vec<String> {
+        match self.reveal_order(&self.view.current_group) {
+            RevealOrder::AsListed => {}
+            RevealOrder::Reversed => hidden.reverse(),
+            RevealOrder::Shuffled => hidden.shuffle(&mut *self.rng.borrow_mut()),
+        }
+        hidden
+    }
+    // Disable the chosen item in its list, if it is still there and enabled
+    fn render_exclude_button(&self, name: &str, chosen: &Chosen) -> Html {
+        let enabled = chosen
+            .idx
+            .and_then(|idx| self.state.lists.get(name)?.get(idx))
+            .map(|item| item.enabled)
+            .unwrap_or(false);
+        if !enabled {
+            return html! {};
+        }
+        let name = name.to_owned();
+        html! {
+            <button disabled=self.board_locked() || self.pick_pinned(&name)
+                onclick=self.link.callback(move |_| Msg::ExcludeChosen(name.clone()))>
+                {"Exclude This"}
+            </button>
+        }
+    }
+    // Chips for leaving items out of the next draw, without disabling them
+    fn render_temp_exclusions(&self, name: &str) -> Html {
+        let items = match self.state.lists.get(name) {
+            Some(items)
+                if self.numeric(name).is_none() && items.iter().any(|item| item.enabled) =>
+            {
+                items
+            }
+            _ => return html! {},
+        };
+        let excluded = self.view.temp_excluded.get(name);
+        let count = excluded.map(BTreeSet::len).unwrap_or_default();
+        let chip = |(idx, item): (usize, &Item)| {
+            let skipped = excluded
+                .map(|excluded| excluded.contains(&idx))
+                .unwrap_or(false);
+            let list = name.to_owned();
+            html! {
+                <button class=if skipped { "chip selected" } else { "chip" }
+                    title=if skipped { "Include in the next draw" } else { "Skip for the next draw only" }
+                    onclick=self.link.callback(move |_| Msg::ToggleTempExcluded { list: list.clone(), idx })>
+                    {item.name.clone().unwrap_or_else(|| format!("{}", idx))}
+                </button>
+            }
+        };
+        let list = name.to_owned();
+        html! {
+            <details class="exclusions">
+                <summary>{match count {
+                    0 => "Skip\u{2026}".to_owned(),
+                    count => format!("Skipping {}", count),
+                }}</summary>
+                {for items.iter().enumerate().filter(|(_, item)| item.enabled).map(chip)}
+                <button disabled=count == 0
+                    onclick=self.link.callback(move |_| Msg::ClearTempExcluded(list.clone()))>
+                    {"Clear"}
+                </button>
+            </details>
+        }
+    }
+    fn current_group_links(&self) -> Vec<String> {
+        self.group_lists(&self.view.current_group)
+            .iter()
+            .filter_map(|list| self.view.cache.get(list))
+            .filter_map(|chosen| chosen.item.link.clone())
+            .collect()
+    }
+    // Pick from every list in every group without touching the history or
+    // consuming anything, so reloading a kiosk doesn't pile up draws.  Lists
+    // with nothing to draw are left unfrozen.
+    fn freeze_all_groups(&mut self) {
+        let mut lists = vec![];
+        for group in self.state.groups.keys() {
+            for list in self.group_lists(group) {
+                if !lists.contains(&list) {
+                    lists.push(list);
+                }
+            }
+        }
+        for name in lists {
+            if self.view.cache.contains_key(&name) {
+                continue;
+            }
+            if let Some(chosen) = self.choose_from_list(&name) {
+                self.view.cache.insert(name, chosen);
+            }
+        }
+    }
+    // Every list in a group and the groups nested in it, each once, in the
+    // order they are shown
+    fn group_lists(&self, name: &str) -> Vec<String> {
+        let mut lists = vec![];
+        self.collect_group_lists(name, 1, &mut lists);
+        lists
+    }
+    fn collect_group_lists(&self, name: &str, depth: usize, lists: &mut Vec<String>) {
+        if depth > MAX_GROUP_DEPTH {
+            return;
+        }
+        for entry in self.state.groups.get(name).into_iter().flatten() {
+            match entry {
+                GroupEntry::List(list) => {
+                    if !lists.contains(list) {
+                        lists.push(list.clone());
+                    }
+                }
+                GroupEntry::Group { group } => self.collect_group_lists(group, depth + 1, lists),
+            }
+        }
+    }
+    fn roll_preview(&self, group: String) -> RollPreview {
+        let mut skip = vec![];
+        self.collect_dangling(&group, 1, &mut skip);
+        let mut roll = vec![];
+        for list in self.group_lists(&group) {
+            let items = match self.state.lists.get(&list) {
+                Some(items) => items,
+                None => continue,
+            };
+            let reason = if self.view.cache.contains_key(&list) {
+                "already drawn"
+            } else if let Some(numeric) = self.numeric(&list) {
+                match numeric.count() {
+                    0 => "has no numbers in its range",
+                    _ => {
+                        roll.push(list);
+                        continue;
+                    }
+                }
+            } else if items.is_empty() {
+                "is empty"
+            } else if !items.iter().any(|item| item.enabled) {
+                "has no enabled items"
+            } else {
+                roll.push(list);
+                continue;
+            };
+            skip.push((list, reason));
+        }
+        RollPreview { group, roll, skip }
+    }
+    fn collect_dangling(&self, name: &str, depth: usize, out: &mut Vec<(String, &'static str)>) {
+        if depth > MAX_GROUP_DEPTH {
+            return;
+        }
+        for entry in self.state.groups.get(name).into_iter().flatten() {
+            match entry {
+                GroupEntry::List(list) if self.is_dangling(entry) => {
+                    out.push((list.clone(), "no longer exists"))
+                }
+                GroupEntry::Group { group } if self.is_dangling(entry) => {
+                    out.push((group.clone(), "no longer exists"))
+                }
+                GroupEntry::Group { group } => self.collect_dangling(group, depth + 1, out),
+                GroupEntry::List(_) => {}
+            }
+        }
+    }
+    fn render_qr(&self) -> Html {
+        if self.view.qr.is_none() {
+            return html! {};
+        }
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                <div class="qr" onclick=self.link.callback(|e: MouseEvent| {
+                    e.stop_propagation();
+                    Msg::Nothing
+                })>
+                    <canvas ref=self.qr_canvas.clone()></canvas>
+                    <p>{"Scan this with the other device's camera to open the board there."}</p>
+                    <button onclick=self.link.callback(|_| Msg::CloseOverlay)>{"Close"}</button>
+                </div>
+            </div>
+        }
+    }
+    fn render_roll_preview(&self) -> Html {
+        let preview = match self.view.roll_preview.as_ref() {
+            Some(preview) => preview,
+            None => return html! {},
+        };
+        let skipped = match preview.skip.is_empty() {
+            true => html! {},
+            false => html! {
+                <>
+                <p>{"Skipped"}</p>
+                <ul class="skipped">
+                    {for preview.skip.iter().map(|(name, reason)| html! {
+                        <li>{format!("{} {}", self.list_label(name), reason)}</li>
+                    })}
+                </ul>
+                </>
+            },
+        };
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseOverlay)>
+                <div class="roll-preview" onclick=self.link.callback(|e: MouseEvent| {
+                    e.stop_propagation();
+                    Msg::Nothing
+                })>
+                    <p>{format!("Rolling {} draws for", preview.group)}</p>
+                    <ul>
+                        {for preview.roll.iter().map(|name| html! { <li>{self.list_label(name)}</li> })}
+                    </ul>
+                    {skipped}
+                    <button disabled=preview.roll.is_empty() onclick=self.link.callback(|_| Msg::RollGroup)>
+                        {"Roll Now"}
+                    </button>
+                    <button onclick=self.link.callback(|_| Msg::CloseOverlay)>{"Cancel"}</button>
+                </div>
+            </div>
+        }
+    }
+    // Whether a group entry names a list or group that no longer exists
+    fn is_dangling(&self, entry: &GroupEntry) -> bool {
+        match entry {
+            GroupEntry::List(name) => !self.state.lists.contains_key(name),
+            GroupEntry::Group { group } => !self.state.groups.contains_key(group),
+        }
+    }
+    fn render_group_entry(&self, entry: &GroupEntry, depth: usize) -> Html {
+        if self.is_dangling(entry) {
+            let (kind, name) = match entry {
+                GroupEntry::List(name) => ("List", name),
+                GroupEntry::Group { group } => ("Group", group),
+            };
+            let entry = entry.clone();
+            return html! {
+                <>
+                <dt>{name}</dt>
+                <dd class="missing">
+                    {format!("{} '{}' no longer exists", kind, name)}
+                    <button onclick=self.link.callback(move |_| Msg::RemoveDanglingRef(entry.clone()))>
+                        {"Remove Reference"}
+                    </button>
+                </dd>
+                </>
+            };
+        }
+        match entry {
+            GroupEntry::List(name) => self.render_group_element(name),
+            GroupEntry::Group { group } if depth < MAX_GROUP_DEPTH => html! {
+                <>
+                <dt class="nested-group">{group}</dt>
+                <dd>
+                    <dl>
+                    {for self.state.groups.get(group).into_iter().flatten()
+                        .map(|entry| self.render_group_entry(entry, depth + 1))}
+                    </dl>
+                </dd>
+                </>
+            },
+            GroupEntry::Group { .. } => html! {},
+        }
+    }
+    fn render_group_element(&self, name: &str) -> Html {
+        let locked = self.board_locked();
+        let name1 = name.to_owned();
+        let name2 = name.to_owned();
+        let name3 = name.to_owned();
+        let name4 = name.to_owned();
+        let hidden = self.view.surprise
+            && !self
+                .view
+                .revealed
+                .contains(&(self.view.current_group.clone(), name.to_owned()));
+        let hovered = name.to_owned();
+        let enter = self
+            .link
+            .callback(move |_| Msg::HoverList(Some(hovered.clone())));
+        let leave = self.link.callback(|_| Msg::HoverList(None));
+        let pinned = self.pick_pinned(name);
+        let toggle_pin = name.to_owned();
+        let pin = html! {
+            <button class=if pinned { "pin selected" } else { "pin" }
+                title=if pinned { "Unpin this pick" } else { "Keep this pick through Unlock All Lists" }
+                onclick=self.link.callback(move |_| Msg::TogglePinnedPick(toggle_pin.clone()))>
+                {"\u{1f4cc}"}
+            </button>
+        };
+        match self.view.cache.get(name) {
+            Some(_) if hidden => html! {
+                <>
+                <dt>{self.list_label(name)}</dt>
+                <dd onmouseenter=enter onmouseleave=leave
+                    onclick=self.link.callback(move |_| Msg::RevealList(name2.clone()))>
+                    <div class="item covered">{"?"}</div>
+                </dd>
+                </>
+            },
+            Some(chosen) => html! {
+                <>
+                <dt>{self.list_label(name)}</dt>
+                <dd class=format!("chosen {}", self.draw_style_class(name, true))
+                    onmouseenter=enter onmouseleave=leave>
+                {self.render_group_pick(name, chosen)}
+                {self.render_confetti(name)}
+                {self.render_exclude_button(name, chosen)}
+                {pin}
+                <button disabled=locked || pinned onclick=self.link.callback(move |_| Msg::RerollList(name1.clone()))>
+                    {"Reroll"}
+                </button>
+                <button onclick=self.link.callback(move |_| Msg::ShowFullscreen(name3.clone()))>
+                    {"Fullscreen"}
+                </button>
+                <button class="delete" disabled=locked || pinned onclick=self.link.callback(move |_| Msg::ThawList(name2.clone()))>
+                    {"Unlock"}
+                </button>
+                {match self.view.last_consumed.contains_key(name) {
+                    true => html! {
+                        <button disabled=locked || pinned onclick=self.link.callback(move |_| Msg::UndoDraw(name4.clone()))>
+                            {"Undo Draw"}
+                        </button>
+                    },
+                    false => html! {},
+                }}
+                </dd>
+                </>
+            },
+            None if self.numeric(name).is_none()
+                && self.on_empty(name) != EmptyPolicy::Keep
+                && self
+                    .state
+                    .lists
+                    .get(name)
+                    .map(Vec::is_empty)
+                    .unwrap_or(false) =>
+            {
+                html! {
+                    <>
+                    <dt>{self.list_label(name)}</dt>
+                    <dd class="empty-list"
+                        onclick=self.link.callback(move |_| Msg::FocusList(name2.clone()))
+                    >
+                        {format!("List '{}' is empty \u{2014} add items", name)}
+                    </dd>
+                    </>
+                }
+            }
+            None => {
+                let item = self.flash_item(name);
+                html! {
+                    <>
+                    <dt>{self.list_label(name)}{self.render_temp_exclusions(name)}</dt>
+                    <dd class=self.draw_style_class(name, false)
+                        onmouseenter=enter onmouseleave=leave
+                        onclick=self.link.callback(move |_| Msg::FreezeList(name2.clone()) )
+                    >{item.render_flash()}
+                    </dd>
+                    </>
+                }
+            }
+        }
+    }
+    // What a flashing list is showing, as the item is now in case it was
+    // edited mid-spin
+    fn flash_item(&self, name: &str) -> Item {
+        match self.view.flash.get(name) {
+            Some(Chosen { idx: Some(idx), .. }) => self
+                .state
+                .lists
+                .get(name)
+                .and_then(|list| list.get(*idx))
+                .cloned()
+                .unwrap_or_default(),
+            Some(chosen) => chosen.item.clone(),
+            None => Item::default(),
+        }
+    }
+    fn list_label(&self, name: &str) -> String {
+        match self.state.list_icons.get(name) {
+            Some(icon) => format!("{} {}", icon, name),
+            None => name.to_owned(),
+        }
+    }
+    fn render_list_name(&self, name: &str) -> Html {
+        let name3 = name.to_owned();
+        let (class, callback) = if name == self.view.current_list {
+            ("selected", self.link.callback(move |_| Msg::BlurList))
+        } else {
+            (
+                "",
+                self.link.callback(move |_| Msg::FocusList(name3.clone())),
+            )
+        };
+        let buttons = if !self.view.current_group.is_empty() {
+            let name1 = name.to_owned();
+            let name2 = name.to_owned();
+            html! {
+                <>
+                <button class="add" onclick=self.link.callback(move |_| Msg::AddToGroup(GroupEntry::List(name1.clone())))>
+                    {"+"}
+                </button>
+                <button class="remove" onclick=self.link.callback(move |_| Msg::RemoveGroupItem(GroupEntry::List(name2.clone())))>
+                    {"-"}
+                </button>
+                </>
+            }
+        } else {
+            html! {<></>}
+        };
+        let name4 = name.to_owned();
+        let name5 = name.to_owned();
+        html! {
+            <li
+                class=class
+                onclick=callback
+                draggable="true"
+                ondragstart=self.link.callback(move |e: DragEvent| {
+                    if let Some(data) = e.data_transfer() {
+                        let _ = data.set_data("text/plain", &name4);
+                    }
+                    Msg::DragList(name4.clone())
+                })
+                ondragover=self.link.callback(|e: DragEvent| {
+                    e.prevent_default();
+                    Msg::Nothing
+                })
+                ondrop=self.link.callback(move |e: DragEvent| {
+                    e.prevent_default();
+                    Msg::DropList(name5.clone())
+                })
+            >
+                {buttons}
+                {self.list_label(name)}
+            </li>
+        }
+    }
+    fn render_lists(&self) -> Html {
+        let mut names = ordered(&self.state.list_order, &self.state.lists);
+        if self.view.lists_by_edited {
+            let modified = |name: &String| self.state.list_modified.get(name).copied();
+            names.sort_by(|a, b| {
+                modified(b)
+                    .partial_cmp(&modified(a))
+                    .unwrap_or(Ordering::Equal)
+            });
+        }
+        html! {
+            <div  class="lists">
+            <p>{"Lists"}</p>
+            <button class=if self.view.lists_by_edited { "selected" } else { "" }
+                title="Most recently edited lists first"
+                onclick=self.link.callback(|_| Msg::ToggleListsByEdited)>
+                {"Recent First"}
+            </button>
+            <ul>
+                {for names.into_iter().map(|name| self.render_list_name(name))}
+                <li>
+                    <input class="edit"
+                        type="text"
+                        placeholder="New List"
+                        value=&self.view.new_list_name
+                        oninput=self.link.callback(move |e: InputData| Msg::UpdateListName(e.value))
+                        onkeypress=self.link.callback(move |e: KeyboardEvent| {
+                            if e.key() == "Enter" { Msg::CreateList } else { Msg::Nothing }
+                    }) />
+                </li>
+                {self.render_list_templates()}
+            </ul>
+            </div>
+        }
+    }
+    fn render_list_templates(&self) -> Html {
+        let template = |(id, label): &(TemplateId, &str)| {
+            let id = *id;
+            html! {
+                <button onclick=self.link.callback(move |_| Msg::InstantiateTemplate(id))>
+                    {label}
+                </button>
+            }
+        };
+        html! {
+            <li class="templates">
+                <details>
+                    <summary>{"From a template"}</summary>
+                    {for LIST_TEMPLATES.iter().map(template)}
+                </details>
+            </li>
+        }
+    }
+    fn render_list_entry(&self, idx: usize, item: &Item, weight: f64, odds: f64) -> Html {
+        let name = item
+            .name
+            .as_ref()
+            .cloned()
+            .unwrap_or_else(|| format!("{}", idx));
+        let idx2 = idx;
+        let selected = Some(idx) == self.view.current_item;
+        let (mut class, callback) = if selected {
+            ("selected".to_owned(), self.link.callback(|_| Msg::BlurItem))
+        } else {
+            (
+                "".to_owned(),
+                self.link.callback(move |_| Msg::FocusItem(idx2)),
+            )
+        };
+        let onkeydown =
+            self.link
+                .callback(move |e: KeyboardEvent| match (e.key().as_str(), selected) {
+                    ("Enter", true) => Msg::BlurItem,
+                    ("Enter", false) => Msg::FocusItem(idx),
+                    _ => Msg::Nothing,
+                });
+        if self
+            .view
+            .flash
+            .get(&self.view.current_list)
+            .and_then(|chosen| chosen.idx)
+            == Some(idx)
+        {
+            class.push_str(" flashing");
+        }
+        let class = item.classes(class.trim());
+        let base_weight = self.item_weight(&self.view.current_list, item);
+        let tiers = self.tiers(&self.view.current_list);
+        let tier = match item
+            .tier
+            .as_ref()
+            .and_then(|tier| tiers.iter().position(|t| t.name == *tier))
+        {
+            // The chip's colour comes from the tier's place in the list
+            Some(idx) => html! {
+                <span class=format!("tier tier-{}", idx.min(DEFAULT_TIERS.len() - 1))>
+                    {&tiers[idx].name}
+                </span>
+            },
+            None => html! {},
+        };
+        let step = |delta: i32, label: &str| {
+            html! {
+                <button class="step" onclick=self.link.callback(move |e: MouseEvent| {
+                    e.stop_propagation();
+                    Msg::StepTickets { idx, delta }
+                })>{label}</button>
+            }
+        };
+        let weight_label = if self.uses_tickets(&self.view.current_list) {
+            let tickets = base_weight.round();
+            html! {
+                <span class="weight" title="Tickets (chance of being drawn next)">
+                    {step(-1, "\u{2212}")}
+                    {format!(
+                        "{} {} ({}%)",
+                        tickets,
+                        if tickets == 1.0 { "ticket" } else { "tickets" },
+                        (odds * 1000.0).round() / 10.0
+                    )}
+                    {step(1, "+")}
+                </span>
+            }
+        } else {
+            html! {
+                <span class="weight" title="Base weight (effective weight)">
+                    {match self.uses_percentages(&self.view.current_list) {
+                        true => format!("{}% ({})", base_weight, weight),
+                        false => format!("{} ({})", base_weight, weight),
+                    }}
+                </span>
+            }
+        };
+        let cooldown = match item.cooldown {
+            0 => html! {},
+            draws => html! {
+                <span class="cooldown" title="Draws until it can come up again">
+                    {format!("\u{23f3}{}", draws)}
+                </span>
+            },
+        };
+        let missing = match self.required(&self.view.current_list) {
+            Some(required) if required.missing(item) => html! {
+                <span class="warning" title="This list expects every item to have one">
+                    {format!("No {}", required.label())}
+                </span>
+            },
+            _ => html! {},
+        };
+        let checkbox = if self.view.multi_select {
+            html! {
+                <input type="checkbox" checked=self.view.selected_items.contains(&idx)
+                    onclick=self.link.callback(move |e: MouseEvent| {
+                        e.stop_propagation();
+                        Msg::ToggleSelectItem(idx)
+                    })
+                />
+            }
+        } else {
+            html! {}
+        };
+        html! {
+            <li class=class tabindex="0"
+                onclick=callback
+                onkeydown=onkeydown
+            >
+                {checkbox}
+                <button onclick=self.link.callback(move |_| Msg::RemoveListItem(idx))>
+                    {"-"}
+                </button>
+                <button class=if item.pinned { "pin selected" } else { "pin" }
+                    title=if item.pinned { "Unpin" } else { "Pin to top" }
+                    onclick=self.link.callback(move |e: MouseEvent| {
+                        e.stop_propagation();
+                        Msg::TogglePinned(idx)
+                    })
+                >
+                    {"\u{1f4cc}"}
+                </button>
+                <button class=if item.position_locked { "lock selected" } else { "lock" }
+                    title=if item.position_locked { "Let Sort and Shuffle move it" } else { "Keep in place when sorting or shuffling" }
+                    onclick=self.link.callback(move |e: MouseEvent| {
+                        e.stop_propagation();
+                        Msg::TogglePositionLocked(idx)
+                    })
+                >
+                    {"\u{1f512}"}
+                </button>
+                {name}
+                {tier}
+                {weight_label}
+                {cooldown}
+                {missing}
+            </li>
+        }
+    }
+    fn render_selection_controls(&self) -> Html {
+        let toggle = html! {
+            <button class=if self.view.multi_select { "selected" } else { "" }
+                onclick=self.link.callback(|_| Msg::ToggleMultiSelect)>
+                {"Select"}
+            </button>
+        };
+        if !self.view.multi_select {
+            return toggle;
+        }
+        html! {
+            <div class="selection">
+                {toggle}
+                <button onclick=self.link.callback(|_| Msg::SelectAllItems)>{"All"}</button>
+                <button onclick=self.link.callback(|_| Msg::SelectNoItems)>{"None"}</button>
+                <button onclick=self.link.callback(|_| Msg::InvertSelection)>{"Invert"}</button>
+                <button class="delete" onclick=self.link.callback(|_| Msg::RemoveSelectedItems)>
+                    {format!("Delete {} Selected", self.view.selected_items.len())}
+                </button>
+            </div>
+        }
+    }
+    fn render_list(&self) -> Html {
+        if let Some(list) = self.state.lists.get(&self.view.current_list) {
+            let name = self.view.current_list.to_owned();
+            let weights = self.effective_weights(&name);
+            let odds = self.list_probabilities(&name);
+            // Pinned items first, then by odds if asked, otherwise in list order
+            let mut entries: Vec<(usize, (&Item, f64))> =
+                list.iter().zip(weights).enumerate().collect();
+            entries.sort_by(|(a, (a_item, _)), (b, (b_item, _))| {
+                let by_odds = match self.view.sort_by_odds {
+                    true => odds[*b].partial_cmp(&odds[*a]).unwrap_or(Ordering::Equal),
+                    false => Ordering::Equal,
+                };
+                b_item.pinned.cmp(&a_item.pinned).then(by_odds)
+            });
+            if let Some(tag) = self.view.tag_filter.as_ref() {
+                entries.retain(|(_, (item, _))| item.tags.contains(tag));
+            }
+            let arrange = html! {
+                <>
+                <button title="Sort by name, leaving locked items in place"
+                    onclick=self.link.callback(|_| Msg::SortList)>
+                    {"Sort A\u{2013}Z"}
+                </button>
+                <button title="Shuffle, leaving locked items in place"
+                    onclick=self.link.callback(|_| Msg::ShuffleList)>
+                    {"Shuffle"}
+                </button>
+                </>
+            };
+            html! {
+                <div class="list" ref=self.list_panel.clone()>
+                <input class="icon" type="text" placeholder="Icon" size=2
+                    value=self.state.list_icons.get(&name).cloned().unwrap_or_default()
+                    oninput=self.link.callback(move |e: InputData| Msg::EditListIcon(e.value))
+                />
+                <p>{&name}</p>
+                {match self.state.list_modified.get(&name) {
+                    Some(time) => html! {
+                        <span class="modified">
+                            {format!("edited {}", format_relative(js_sys::Date::now() - time))}
+                        </span>
+                    },
+                    None => html! {},
+                }}
+                {match self.state.list_max.get(&name) {
+                    Some(max) => html! {<span class="count">{format!("{}/{}", list.len(), max)}</span>},
+                    None => html! {},
+                }}
+                {match (self.required(&name), self.missing_count(&name)) {
+                    (Some(required), missing) if missing > 0 => html! {
+                        <span class="count warning">{format!("{} without {}", missing, required.label())}</span>
+                    },
+                    _ => html! {},
+                }}
+                <button class="delete" onclick=self.link.callback(move |_| Msg::RemoveList(name.clone()))>
+                    {"Delete List"}
+                </button>
+                {self.render_list_options(&self.view.current_list)}
+                {self.render_roll_until(&self.view.current_list)}
+                {self.render_selection_controls()}
+                <button class=if self.view.sort_by_odds { "selected" } else { "" }
+                    onclick=self.link.callback(|_| Msg::ToggleSortByOdds)>
+                    {"Most Likely First"}
+                </button>
+                {arrange}
+                <button class=if self.view.focus_list { "selected" } else { "" }
+                    title="Dim everything but this list"
+                    onclick=self.link.callback(|_| Msg::ToggleFocusList)>
+                    {"Focus"}
+                </button>
+                <ul class="entries">
+                    {for entries.into_iter().map(|(idx, (item, weight))| self.render_list_entry(idx, item, weight, odds[idx]))}
+                    <li>
+                        <button onclick=self.link.callback(move |_| Msg::CreateItem)>
+                            {"+"}
+                        </button>
+                    </li>
+                </ul>
+                {self.render_tags()}
+                {self.render_ranking()}
+                {self.render_bulk_images()}
+                {self.render_find_replace()}
+                </div>
+            }
+        } else {
+            html! {
+                <div class="list" ref=self.list_panel.clone()></div>
+            }
+        }
+    }
+    // Every tag in the current list with how many items have it
+    fn render_tags(&self) -> Html {
+        let mut counts: BTreeMap<&String, usize> = BTreeMap::new();
+        for tag in self
+            .get_current_list()
+            .into_iter()
+            .flatten()
+            .flat_map(|item| &item.tags)
+        {
+            *counts.entry(tag).or_default() += 1;
+        }
+        if counts.is_empty() {
+            return html! {};
+        }
+        let tag = |(tag, count): (&String, usize)| {
+            let filtering = self.view.tag_filter.as_ref() == Some(tag);
+            let filter = match filtering {
+                true => None,
+                false => Some(tag.clone()),
+            };
+            let from = tag.clone();
+            let delete = tag.clone();
+            html! {
+                <li>
+                    <button class=if filtering { "selected" } else { "" }
+                        title="Show only items with this tag"
+                        onclick=self.link.callback(move |_| Msg::FilterByTag(filter.clone()))>
+                        {format!("{} ({})", tag, count)}
+                    </button>
+                    <input class="rename" type="text" placeholder="Rename" value=tag
+                        onchange=self.link.callback(move |e: ChangeData| match e {
+                            ChangeData::Value(to) => Msg::RenameTag { from: from.clone(), to },
+                            _ => Msg::Nothing,
+                        })
+                    />
+                    <button class="delete" title="Remove from every item"
+                        onclick=self.link.callback(move |_| Msg::DeleteTag(delete.clone()))>
+                        {"\u{d7}"}
+                    </button>
+                </li>
+            }
+        };
+        html! {
+            <details class="tags">
+                <summary>{format!("Tags ({})", counts.len())}</summary>
+                <ul>
+                    {for counts.into_iter().map(tag)}
+                </ul>
+            </details>
+        }
+    }
+    fn render_ranking(&self) -> Html {
+        let name = self.view.current_list.clone();
+        let rank = html! {
+            <button onclick=self.link.callback(move |_| Msg::RankList(name.clone()))>
+                {"Rank All"}
+            </button>
+        };
+        match self.view.ranking.as_ref() {
+            Some((list, ranked, drawable)) if *list == self.view.current_list => html! {
+                <div class="ranking">
+                    {rank}
+                    <button onclick=self.link.callback(|_| Msg::CloseRanking)>{"Close"}</button>
+                    <ol>
+                        {for ranked.iter().enumerate().map(|(idx, item)| match idx < *drawable {
+                            true => html! {
+                                <li class=item.classes("")>{item_label(&item.name)}</li>
+                            },
+                            false => html! {
+                                <li class=item.classes("undrawable")
+                                    title="Can't be drawn now: disabled, cooling down or weighing nothing"
+                                >
+                                    {item_label(&item.name)}
+                                </li>
+                            },
+                        })}
+                    </ol>
+                </div>
+            },
+            _ => rank,
+        }
+    }
+    // Each item in the current list that replacing would change, with its
+    // text before and after
+    fn replacements(
+        &self,
+        field: ItemField,
+        find: &str,
+        with: &str,
+    ) -> Vec<(usize, String, String)> {
+        if find.is_empty() {
+            return vec![];
+        }
+        let ignore_case = self.view.replace_ignore_case;
+        self.get_current_list()
+            .into_iter()
+            .flatten()
+            .enumerate()
+            .filter_map(|(idx, item)| {
+                let text = item.field(field)?;
+                let replaced = replace_matches(text, find, with, ignore_case);
+                match replaced != *text {
+                    true => Some((idx, text.clone(), replaced)),
+                    false => None,
+                }
+            })
+            .collect()
+    }
+    fn render_find_replace(&self) -> Html {
+        let field = self.view.replace_field;
+        let changes = self.replacements(field, &self.view.replace_find, &self.view.replace_with);
+        let option = |value: &str, label: &str, this: ItemField| {
+            html! {
+                <option value=value selected=field == this>{label}</option>
+            }
+        };
+        let find = self.view.replace_find.clone();
+        let replace = self.view.replace_with.clone();
+        let preview = html! {
+            <ul class="preview">
+                {for changes.iter().map(|(idx, before, after)| html! {
+                    <li>
+                        <span class="list-name">{format!("{}.", idx)}</span>
+                        <del>{before}</del>{" \u{2192} "}<ins>{after}</ins>
+                    </li>
+                })}
+            </ul>
+        };
+        html! {
+            <details class="find-replace">
+                <summary>{"Find and Replace"}</summary>
+                <select onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SetReplaceField(match select.value().as_str() {
+                        "link" => ItemField::Link,
+                        "comment" => ItemField::Comment,
+                        _ => ItemField::Name,
+                    }),
+                    _ => Msg::Nothing,
+                })>
+                    {option("name", "Names", ItemField::Name)}
+                    {option("link", "Links", ItemField::Link)}
+                    {option("comment", "Comments", ItemField::Comment)}
+                </select>
+                <input class="edit" type="text" placeholder="Find" value=&self.view.replace_find
+                    oninput=self.link.callback(|e: InputData| Msg::UpdateReplaceFind(e.value))
+                />
+                <input class="edit" type="text" placeholder="Replace with" value=&self.view.replace_with
+                    oninput=self.link.callback(|e: InputData| Msg::UpdateReplaceWith(e.value))
+                />
+                <label>
+                    <input type="checkbox" checked=self.view.replace_ignore_case
+                        onclick=self.link.callback(|_| Msg::ToggleReplaceIgnoreCase)
+                    />
+                    {"Ignore case"}
+                </label>
+                <button disabled=changes.is_empty()
+                    onclick=self.link.callback(move |_| Msg::ReplaceInList {
+                        field,
+                        find: find.clone(),
+                        replace: replace.clone(),
+                    })
+                >
+                    {format!("Replace in {} Items", changes.len())}
+                </button>
+                {preview}
+            </details>
+        }
+    }
+    fn render_bulk_images(&self) -> Html {
+        let urls = self.view.bulk_images.clone();
+        html! {
+            <div class="bulk-images">
+                <textarea class="edit" placeholder="Image URLs, one per line"
+                    value=&self.view.bulk_images
+                    oninput=self.link.callback(|e: InputData| Msg::UpdateBulkImages(e.value))
+                />
+                <button onclick=self.link.callback(move |_| Msg::BulkAddImages(urls.clone()))>
+                    {"Add Images"}
+                </button>
+            </div>
+        }
+    }
+    fn render_copy_to_list(&self, idx: usize, item: &Item) -> Html {
+        let targets: Vec<&String> = ordered(&self.state.list_order, &self.state.lists)
+            .into_iter()
+            .filter(|name| **name != self.view.current_list)
+            .collect();
+        if targets.is_empty() {
+            return html! {};
+        }
+        let callback = self.link.callback(move |e: ChangeData| match e {
+            ChangeData::Select(select) if !select.value().is_empty() => Msg::CopyItemToList {
+                idx,
+                target: select.value(),
+            },
+            _ => Msg::Nothing,
+        });
+        html! {
+            <select class="copy-to-list" onchange=callback>
+                <option value="" selected=true>{"Copy to list\u{2026}"}</option>
+                {for targets.into_iter().map(|name| html! {
+                    <option value=name disabled=!self.can_copy_to(item, name)>
+                        {self.list_label(name)}
+                    </option>
+                })}
+            </select>
+        }
+    }
+    // Lists that are full or already hold an identical item don't take copies
+    fn can_copy_to(&self, item: &Item, target: &str) -> bool {
+        match self.state.lists.get(target) {
+            Some(list) => {
+                !self.list_is_full(target) && !list.iter().any(|other| other.duplicates(item))
+            }
+            None => false,
+        }
+    }
+    fn render_roll_until(&self, name: &str) -> Html {
+        let by_weight = self.view.roll_until_by_weight;
+        let value = self.view.roll_until.trim();
+        let predicate = match by_weight {
+            true => value.parse().ok().map(RollPredicate::MinWeight),
+            false if value.is_empty() => None,
+            false => Some(RollPredicate::HasTag(value.to_owned())),
+        };
+        let list = name.to_owned();
+        let disabled = predicate.is_none() || self.board_locked();
+        let roll = self.link.callback(move |_| match predicate.clone() {
+            Some(predicate) => Msg::RollUntil {
+                list: list.clone(),
+                predicate,
+            },
+            None => Msg::Nothing,
+        });
+        html! {
+            <span class="roll-until">
+                <select onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SetRollUntilByWeight(select.value() == "weight"),
+                    _ => Msg::Nothing,
+                })>
+                    <option value="tag" selected=!by_weight>{"Tag"}</option>
+                    <option value="weight" selected=by_weight>{"Weight at least"}</option>
+                </select>
+                <input class="edit" type="text" value=&self.view.roll_until
+                    oninput=self.link.callback(|e: InputData| Msg::UpdateRollUntil(e.value))
+                />
+                <button disabled=disabled onclick=roll>{"Roll Until"}</button>
+            </span>
+        }
+    }
+    fn render_list_options(&self, name: &str) -> Html {
+        let name1 = name.to_owned();
+        let name2 = name.to_owned();
+        let name3 = name.to_owned();
+        let name4 = name.to_owned();
+        let percentages = self.uses_percentages(name);
+        let percent_total: f64 = self.enabled_weights(name).iter().sum();
+        html! {
+            <>
+            <input class="max" type="number" min="0" placeholder="Max items"
+                value=self.state.list_max.get(name).map(|max| max.to_string()).unwrap_or_default()
+                oninput=self.link.callback(move |e: InputData| Msg::EditListMax(e.value))
+            />
+            <label>
+                <input type="checkbox" checked=self.consumes_on_roll(name)
+                    onclick=self.link.callback(move |_| Msg::ToggleConsumeOnRoll(name1.clone()))
+                />
+                {"Remove items when drawn"}
+            </label>
+            <label>
+                <input type="checkbox" checked=self.in_rotation(name)
+                    onclick=self.link.callback(move |_| Msg::ToggleRotation(name2.clone()))
+                />
+                {"Rotation (least recently chosen first)"}
+            </label>
+            <label>
+                <input type="checkbox" checked=percentages
+                    onclick=self.link.callback(move |_| Msg::TogglePercentages(name3.clone()))
+                />
+                {"Weights are percentages"}
+            </label>
+            {self.render_tickets(name)}
+            {self.render_numeric(name)}
+            {self.render_required(name)}
+            {self.render_empty_policy(name)}
+            {self.render_draw_style(name)}
+            {self.render_draw_mode(name)}
+            {self.render_normalize(name)}
+            {self.render_tiers(name)}
+            {self.render_cooldown(name)}
+            {self.render_display_template(name)}
+            {if percentages && (percent_total - 100.0).abs() > 0.05 {
+                html! {
+                    <p class="warning">
+                        {format!("Percentages add up to {}%, not 100%", (percent_total * 100.0).round() / 100.0)}
+                        <button onclick=self.link.callback(move |_| Msg::NormalizeWeights(name4.clone()))>
+                            {"Make Them Add Up to 100%"}
+                        </button>
+                    </p>
+                }
+            } else {
+                html! {}
+            }}
+            </>
+        }
+    }
+    fn render_numeric(&self, name: &str) -> Html {
+        let toggle = name.to_owned();
+        let numeric = self.numeric(name);
+        let bound = |bound: NumericBound, label: &str, value: i64| {
+            let list = name.to_owned();
+            html! {
+                <label>
+                    {label}
+                    <input class="bound" type="number" step="1" value=value.to_string()
+                        onchange=self.link.callback(move |e: ChangeData| match e {
+                            ChangeData::Value(text) => Msg::EditNumeric {
+                                list: list.clone(),
+                                bound,
+                                text,
+                            },
+                            _ => Msg::Nothing,
+                        })
+                    />
+                </label>
+            }
+        };
+        let range = match numeric {
+            Some(numeric) => html! {
+                <>
+                {bound(NumericBound::Min, "From", numeric.min)}
+                {bound(NumericBound::Max, "to", numeric.max)}
+                {bound(NumericBound::Step, "by", numeric.step)}
+                {match numeric.count() {
+                    0 => html! { <span class="count warning">{" (no numbers to roll)"}</span> },
+                    count => html! { <span class="count">{format!(" ({} numbers)", count)}</span> },
+                }}
+                </>
+            },
+            None => html! {},
+        };
+        html! {
+            <div class="numeric">
+                <label>
+                    <input type="checkbox" checked=numeric.is_some()
+                        onclick=self.link.callback(move |_| Msg::ToggleNumeric(toggle.clone()))
+                    />
+                    {"Roll a number instead of an item"}
+                </label>
+                {range}
+            </div>
+        }
+    }
+    fn render_tickets(&self, name: &str) -> Html {
+        let tickets = self.uses_tickets(name);
+        let total: f64 = self.state.lists[name]
+            .iter()
+            .filter(|item| item.enabled)
+            .map(Item::base_weight)
+            .sum();
+        let list = name.to_owned();
+        html! {
+            <label>
+                <input type="checkbox" checked=tickets
+                    onclick=self.link.callback(move |_| Msg::ToggleTickets(list.clone()))
+                />
+                {"Weights are raffle tickets"}
+                {if tickets {
+                    html! { <span class="count">{format!(" ({} in the draw)", total)}</span> }
+                } else {
+                    html! {}
+                }}
+            </label>
+        }
+    }
+    fn render_tiers(&self, name: &str) -> Html {
+        html! {
+            <details class="tiers">
+                <summary>{"Tier Weights"}</summary>
+                {for self.tiers(name).into_iter().enumerate().map(|(idx, tier)| {
+                    let list = name.to_owned();
+                    html! {
+                        <label>
+                            <span class=format!("tier tier-{}", idx.min(DEFAULT_TIERS.len() - 1))>{&tier.name}</span>
+                            <input class="tier-weight" type="number" min="0" step="any"
+                                value=tier.weight.to_string()
+                                oninput=self.link.callback(move |e: InputData| Msg::EditTierWeight {
+                                    list: list.clone(),
+                                    tier: idx,
+                                    text: e.value,
+                                })
+                            />
+                        </label>
+                    }
+                })}
+            </details>
+        }
+    }
+    fn render_normalize(&self, name: &str) -> Html {
+        let list = name.to_owned();
+        let target = match self.uses_percentages(name) {
+            true => html! {},
+            false => html! {
+                <select class="normalize-to" onchange=self.link.callback(|e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SetNormalizeToOne(select.value() == "1"),
+                    _ => Msg::Nothing,
+                })>
+                    <option value="100" selected=!self.view.normalize_to_one>{"to 100"}</option>
+                    <option value="1" selected=self.view.normalize_to_one>{"to 1"}</option>
+                </select>
+            },
+        };
+        html! {
+            <span class="normalize">
+                <button onclick=self.link.callback(move |_| Msg::NormalizeWeights(list.clone()))>
+                    {"Normalize Weights"}
+                </button>
+                {target}
+            </span>
+        }
+    }
+    fn render_display_template(&self, name: &str) -> Html {
+        let placeholders: Vec<String> = template::PLACEHOLDERS
+            .iter()
+            .map(|(placeholder, _)| format!("{{{}}}", placeholder))
+            .collect();
+        html! {
+            <input class="template" type="text" placeholder="Display template"
+                title=format!("How a drawn item is shown, using {}", placeholders.join(" "))
+                value=self.template(name).unwrap_or_default()
+                oninput=self.link.callback(|e: InputData| Msg::EditListTemplate(e.value))
+            />
+        }
+    }
+    fn render_cooldown(&self, name: &str) -> Html {
+        let cooling = self.state.lists[name].iter().any(|item| item.cooldown > 0);
+        let list = name.to_owned();
+        let reset = match cooling {
+            true => html! {
+                <button onclick=self.link.callback(move |_| Msg::ResetCooldowns(list.clone()))>
+                    {"Reset Cooldowns"}
+                </button>
+            },
+            false => html! {},
+        };
+        let cooldown = match self.cooldown(name) {
+            0 => String::new(),
+            draws => draws.to_string(),
+        };
+        html! {
+            <>
+            <input class="cooldown" type="number" min="0" placeholder="Cooldown"
+                title="Draws an item sits out after being drawn"
+                value=cooldown
+                oninput=self.link.callback(|e: InputData| Msg::EditListCooldown(e.value))
+            />
+            {reset}
+            </>
+        }
+    }
+    fn render_required(&self, name: &str) -> Html {
+        let required = self.required(name);
+        let option = |value: &str, label: &str, this: Option<Required>| {
+            html! {
+                <option value=value selected=required == this>{label}</option>
+            }
+        };
+        let list = name.to_owned();
+        let warn = match required {
+            Some(_) => {
+                let list = name.to_owned();
+                html! {
+                    <label>
+                        <input type="checkbox" checked=self.warns_missing(name)
+                            onclick=self.link.callback(move |_| Msg::ToggleWarnMissing(list.clone()))
+                        />
+                        {"Warn before drawing"}
+                    </label>
+                }
+            }
+            None => html! {},
+        };
+        html! {
+            <>
+            <select class="required" onchange=self.link.callback(move |e: ChangeData| match e {
+                ChangeData::Select(select) => Msg::SetRequired {
+                    list: list.clone(),
+                    required: match select.value().as_str() {
+                        "image" => Some(Required::Image),
+                        "link" => Some(Required::Link),
+                        _ => None,
+                    },
+                },
+                _ => Msg::Nothing,
+            })>
+                {option("", "Nothing required", None)}
+                {option("image", "Require an image", Some(Required::Image))}
+                {option("link", "Require a link", Some(Required::Link))}
+            </select>
+            {warn}
+            </>
+        }
+    }
+    fn render_empty_policy(&self, name: &str) -> Html {
+        let policy = self.on_empty(name);
+        let option = |value: &str, label: &str, this: EmptyPolicy| {
+            html! {
+                <option value=value selected=policy == this>{label}</option>
+            }
+        };
+        let list = name.to_owned();
+        html! {
+            <select class="on-empty" title="What groups show once the last item is deleted"
+                onchange=self.link.callback(move |e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SetEmptyPolicy {
+                        list: list.clone(),
+                        policy: match select.value().as_str() {
+                            "remove_from_groups" => EmptyPolicy::RemoveFromGroups,
+                            "keep" => EmptyPolicy::Keep,
+                            _ => EmptyPolicy::Placeholder,
+                        },
+                    },
+                    _ => Msg::Nothing,
+                })>
+                {option("placeholder", "When empty, say so in groups", EmptyPolicy::Placeholder)}
+                {option("remove_from_groups", "When empty, remove from groups", EmptyPolicy::RemoveFromGroups)}
+                {option("keep", "When empty, keep in groups as is", EmptyPolicy::Keep)}
+            </select>
+        }
+    }
+    fn render_draw_style(&self, name: &str) -> Html {
+        let style = self.draw_style(name);
+        let option = |value: &str, label: &str, this: DrawStyle| {
+            html! {
+                <option value=value selected=style == this>{label}</option>
+            }
+        };
+        let list = name.to_owned();
+        html! {
+            <select class="draw-style" title="How picks from this list are revealed"
+                onchange=self.link.callback(move |e: ChangeData| match e {
+                    ChangeData::Select(select) => Msg::SetDrawStyle {
+                        list: list.clone(),
+                        style: match select.value().as_str() {
+                            "fade" => DrawStyle::Fade,
+                            "flip" => DrawStyle::Flip,
+                            "scroll" => DrawStyle::Scroll,
+                            _ => DrawStyle::Flash,
+                        },
+                    },
+                    _ => Msg::Nothing,
+                })>
+                {option("flash", "Reveal like a slot machine", DrawStyle::Flash)}
+                {option("fade", "Reveal with a fade", DrawStyle::Fade)}
+                {option("flip", "Reveal by flipping a card", DrawStyle::Flip)}
+                {option("scroll", "Reveal by scrolling a reel", DrawStyle::Scroll)}
+            </select>
+        }
+    }
+    fn render_draw_mode(&self, name: &str) -> Html {
+        let mode = self.draw_mode(name);
+        let option = |value: &str, label: &str, this: DrawMode| {
+            html! {
+                <option value=value selected=mode == this>{label}</option>
+            }
+        };
+        let list = name.to_owned();
+        html! {
+            <select class="draw-mode" onchange=self.link.callback(move |e: ChangeData| match e {
+                ChangeData::Select(select) => Msg::SetDrawMode {
+                    list: list.clone(),
+                    mode: match select.value().as_str() {
+                        "uniform" => DrawMode::Uniform,
+                        "bag" => DrawMode::Bag,
+                        _ => DrawMode::Weighted,
+                    },
+                },
+                _ => Msg::Nothing,
+            })>
+                {option("weighted", "Draw by weight", DrawMode::Weighted)}
+                {option("uniform", "Draw evenly", DrawMode::Uniform)}
+                {option("bag", "Draw each item once per cycle", DrawMode::Bag)}
+            </select>
+        }
+    }
+    fn render_edit_item(&self) -> Html {
+        if self.state.item_editor_dialog {
+            return html! {
+                <div class="edit-item"></div>
+            };
+        }
+        if let Some((idx, item)) = self.get_current_index_and_item() {
+            html! {
+                <div class="edit-item">
+                {item.render_edit(
+                    &self.link,
+                    self.uses_percentages(&self.view.current_list),
+                    &self.view.item_problems,
+                    &self.tiers(&self.view.current_list),
+                )}
+                {self.render_copy_to_list(idx, item)}
+                </div>
+            }
+        } else {
+            html! {
                 <div class="edit-item"></div>
             }
         }
     }
-    fn choose_from_list(&self, name: &str) -> Item {
-        let mut rng: OsRng = Default::default();
-        let item: Item = self
+    fn render_edit_modal(&self) -> Html {
+        let (idx, item) = match self.get_current_index_and_item() {
+            Some(current) if self.edit_modal_showing() => current,
+            _ => return html! {},
+        };
+        let label = match &item.name {
+            Some(name) => format!("Edit {}", name),
+            None => "Edit item".to_owned(),
+        };
+        html! {
+            <div class="overlay" onclick=self.link.callback(|_| Msg::CloseEditModal)>
+                <div class="edit-modal" role="dialog" aria-modal="true" aria-label=label
+                    ref=self.edit_modal.clone()
+                    onclick=self.link.callback(|e: MouseEvent| {
+                        e.stop_propagation();
+                        Msg::Nothing
+                    })
+                >
+                    {item.render_edit(
+                        &self.link,
+                        self.uses_percentages(&self.view.current_list),
+                        &self.view.item_problems,
+                        &self.tiers(&self.view.current_list),
+                    )}
+                    {self.render_copy_to_list(idx, item)}
+                    <button onclick=self.link.callback(|_| Msg::CloseEditModal)>
+                        {"Done"}
+                    </button>
+                </div>
+            </div>
+        }
+    }
+    fn edit_modal_showing(&self) -> bool {
+        self.view.edit_modal_open
+            && self.state.item_editor_dialog
+            && self.get_current_index_and_item().is_some()
+    }
+    // Show the editor as a dialog, unless it lives in the side pane, and move
+    // focus into it once it has rendered
+    fn open_item_editor(&mut self) {
+        if self.mirror || !self.state.item_editor_dialog || self.view.current_item.is_none() {
+            return;
+        }
+        if !self.view.edit_modal_open {
+            self.edit_modal_return = web_sys::window()
+                .and_then(|w| w.document())
+                .and_then(|document| document.active_element())
+                .and_then(|element| element.dyn_into::<HtmlElement>().ok());
+            self.view.edit_modal_open = true;
+        }
+        self._focus_edit_modal = Some(
+            self.render
+                .request_animation_frame(self.link.callback(|_| Msg::FocusEditModal)),
+        );
+    }
+    fn close_item_editor(&mut self) {
+        if !self.view.edit_modal_open {
+            return;
+        }
+        self.view.edit_modal_open = false;
+        self._focus_edit_modal = Some(
+            self.render
+                .request_animation_frame(self.link.callback(|_| Msg::RestoreEditFocus)),
+        );
+    }
+    // Wrap Tab and Shift+Tab around the dialog's first and last controls
+    fn trap_focus(&self, e: &KeyboardEvent) {
+        let modal = match self.edit_modal.cast::<Element>() {
+            Some(modal) => modal,
+            None => return,
+        };
+        let focusable = focusable_in(&modal);
+        let (first, last) = match (focusable.first(), focusable.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => {
+                e.prevent_default();
+                return;
+            }
+        };
+        let active = web_sys::window()
+            .and_then(|w| w.document())
+            .and_then(|document| document.active_element());
+        let inside = active
+            .as_ref()
+            .map(|element| modal.contains(Some(element)))
+            .unwrap_or_default();
+        let target = if !inside {
+            Some(first)
+        } else if e.shift_key() && active.as_ref() == Some(first.as_ref()) {
+            Some(last)
+        } else if !e.shift_key() && active.as_ref() == Some(last.as_ref()) {
+            Some(first)
+        } else {
+            None
+        };
+        if let Some(target) = target {
+            e.prevent_default();
+            let _ = target.focus();
+        }
+    }
+    fn full_message(&self, name: &str) -> String {
+        format!(
+            "List {} already has its maximum of {} items",
+            name, self.state.list_max[name]
+        )
+    }
+    fn list_is_full(&self, name: &str) -> bool {
+        match (self.state.lists.get(name), self.state.list_max.get(name)) {
+            (Some(list), Some(max)) => list.len() >= *max,
+            _ => false,
+        }
+    }
+    // Whether the flashing candidates should change on this Tick
+    fn spin_due(&mut self) -> bool {
+        let now = js_sys::Date::now();
+        if self.view.spin_start == 0.0 {
+            self.view.spin_start = now;
+        }
+        let unshown = self
+            .group_lists(&self.view.current_group)
+            .iter()
+            .any(|name| !self.view.cache.contains_key(name) && !self.view.flash.contains_key(name));
+        let duration = self.state.spin.duration_ms;
+        let elapsed = now - self.view.spin_start;
+        let interval = match duration > 0.0 {
+            true if elapsed >= duration => f64::INFINITY,
+            true => {
+                let slowed = self.state.spin.easing.apply(elapsed / duration);
+                TICK_MS as f64 + (SPIN_SLOWEST_MS - TICK_MS as f64) * slowed
+            }
+            false => 0.0,
+        };
+        // Lists with nothing showing yet always get a candidate
+        if unshown || now - self.view.last_flash >= interval {
+            self.view.last_flash = now;
+            true
+        } else {
+            false
+        }
+    }
+    // Pick a new item to show for every unfrozen list in the current group
+    fn roll_flash(&mut self) {
+        let flash = self
+            .group_lists(&self.view.current_group)
+            .into_iter()
+            .filter(|name| !self.view.cache.contains_key(name))
+            .filter_map(|name| {
+                let chosen = self.choose_from_list(&name)?;
+                Some((name, chosen))
+            })
+            .collect();
+        self.view.flash = flash;
+    }
+    fn consumes_on_roll(&self, name: &str) -> bool {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.consume_on_roll)
+            .unwrap_or_default()
+    }
+    // Freeze a pick for `name`, removing it from the list if the list consumes
+    // items when drawn.
+    fn draw(&mut self, name: String) {
+        self.draw_until(name, None);
+    }
+    // Draw for `name`, drawing again until the pick matches `until`, up to
+    // ROLL_UNTIL_LIMIT times.  Returns how many draws it took and whether the
+    // last one matched, or None if there was nothing to draw.
+    fn draw_until(&mut self, name: String, until: Option<&RollPredicate>) -> Option<(usize, bool)> {
+        self.view
+            .revealed
+            .remove(&(self.view.current_group.clone(), name.clone()));
+        if self.numeric(&name).is_some() {
+            return self.draw_number(name, until);
+        }
+        self.refill_bag(&name);
+        let (algorithm, weights) = self.draw_weights(&name);
+        // Each draw gets its own seed so it can be repeated later
+        let seed = self.rng.borrow_mut().gen();
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        let mut attempts = 0;
+        let (idx, matched) = loop {
+            attempts += 1;
+            let idx = match weighted_index(&weights, &mut rng) {
+                Some(idx) => idx,
+                // Nothing to freeze; the group keeps showing the empty list warning
+                None if weights.is_empty() => return None,
+                None => {
+                    if !self.consumes_on_roll(&name) {
+                        let chosen = Chosen {
+                            item: Item::default(),
+                            idx: None,
+                        };
+                        self.view.cache.insert(name, chosen);
+                    }
+                    return None;
+                }
+            };
+            let matched = until
+                .map(|until| {
+                    let item = &self.state.lists[&name][idx];
+                    until.matches(item, self.item_weight(&name, item))
+                })
+                .unwrap_or(true);
+            if matched || attempts >= ROLL_UNTIL_LIMIT {
+                break (idx, matched);
+            }
+        };
+        let note = until.map(|until| format!("rolled until {}", until.describe()));
+        self.finish_draw(name, idx, seed, attempts, (algorithm, weights), note);
+        Some((attempts, matched))
+    }
+    // Draw for `name` with a seed chosen beforehand, as Roll Within Cap does
+    // once it has found seeds that fit.  `note` says why in the proof.
+    fn draw_seeded(&mut self, name: String, seed: u64, note: &str) {
+        self.view
+            .revealed
+            .remove(&(self.view.current_group.clone(), name.clone()));
+        if let Some(numeric) = self.numeric(&name) {
+            if let Some(item) = numeric.roll(&mut ChaCha20Rng::seed_from_u64(seed)) {
+                self.finish_number(name, item);
+            }
+            return;
+        }
+        self.refill_bag(&name);
+        let (algorithm, weights) = self.draw_weights(&name);
+        if let Some(idx) = weighted_index(&weights, &mut ChaCha20Rng::seed_from_u64(seed)) {
+            self.finish_draw(
+                name,
+                idx,
+                seed,
+                1,
+                (algorithm, weights),
+                Some(note.to_owned()),
+            );
+        }
+    }
+    // What draw_seeded would pick, without drawing it
+    fn seeded_pick(&self, name: &str, seed: u64) -> Option<Item> {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        if let Some(numeric) = self.numeric(name) {
+            return numeric.roll(&mut rng);
+        }
+        let (_, weights) = self.draw_weights(name);
+        let idx = weighted_index(&weights, &mut rng)?;
+        Some(self.state.lists[name][idx].clone())
+    }
+    // Freeze item `idx` as `name`'s pick: record it, start its cooldown and
+    // consume it if the list does that
+    fn finish_draw(
+        &mut self,
+        name: String,
+        idx: usize,
+        seed: u64,
+        attempts: usize,
+        (algorithm, weights): (&'static str, Vec<f64>),
+        note: Option<String>,
+    ) {
+        if algorithm == "bag" {
+            self.state
+                .list_settings
+                .entry(name.clone())
+                .or_default()
+                .bag
+                .insert(idx);
+        }
+        let consume = self.consumes_on_roll(&name);
+        let cooldown = self.cooldown(&name);
+        let list = self.state.lists.get_mut(&name).unwrap();
+        let now = js_sys::Date::now();
+        list[idx].last_chosen = Some(now);
+        for item in list.iter_mut() {
+            item.cooldown = item.cooldown.saturating_sub(1);
+        }
+        list[idx].cooldown = cooldown;
+        let proof = match self.state.fair_draws {
+            true => Some(DrawProof {
+                seed,
+                algorithm: match note {
+                    Some(note) => format!("{} {}, {}", DRAW_RNG, algorithm, note),
+                    None => format!("{} {}", DRAW_RNG, algorithm),
+                },
+                attempts,
+                candidates: list
+                    .iter()
+                    .map(|item| item.name.clone())
+                    .zip(weights)
+                    .collect(),
+                winner: idx,
+            }),
+            false => None,
+        };
+        let draw = Draw {
+            time: now,
+            list: name.clone(),
+            item: list[idx].name.clone(),
+            proof,
+        };
+        self.record_draw(draw);
+        self.view.revealing.insert(name.clone(), now);
+        self.view.temp_excluded.remove(&name);
+        let list = self.state.lists.get_mut(&name).unwrap();
+        if !consume {
+            let chosen = Chosen {
+                item: list[idx].clone(),
+                idx: Some(idx),
+            };
+            self.view.cache.insert(name, chosen);
+            return;
+        }
+        // Even if that empties the list; see apply_empty_policy
+        let item = list.remove(idx);
+        self.item_removed(&name, idx);
+        let chosen = Chosen {
+            item: item.clone(),
+            idx: None,
+        };
+        self.view.cache.insert(name.clone(), chosen);
+        self.view.last_consumed.insert(name, (idx, item));
+    }
+    // draw_until for a numeric list.  There are no items to list as
+    // candidates, so these draws carry no proof.
+    fn draw_number(
+        &mut self,
+        name: String,
+        until: Option<&RollPredicate>,
+    ) -> Option<(usize, bool)> {
+        let mut attempts = 0;
+        let (item, matched) = loop {
+            attempts += 1;
+            let item = self.choose_from_list(&name)?.item;
+            let matched = until
+                .map(|until| until.matches(&item, self.item_weight(&name, &item)))
+                .unwrap_or(true);
+            if matched || attempts >= ROLL_UNTIL_LIMIT {
+                break (item, matched);
+            }
+        };
+        self.finish_number(name, item);
+        Some((attempts, matched))
+    }
+    fn finish_number(&mut self, name: String, item: Item) {
+        let now = js_sys::Date::now();
+        self.record_draw(Draw {
+            time: now,
+            list: name.clone(),
+            item: item.name.clone(),
+            proof: None,
+        });
+        self.view.revealing.insert(name.clone(), now);
+        self.view.cache.insert(name, Chosen { item, idx: None });
+    }
+    // Drop draws older than the board's history_ttl_days
+    fn expire_history(&mut self) {
+        if self.state.history_ttl_days == 0 {
+            return;
+        }
+        let cutoff = js_sys::Date::now() - self.state.history_ttl_days as f64 * DAY_MS;
+        if self.state.history.iter().any(|draw| draw.time < cutoff) {
+            self.state.history.retain(|draw| draw.time >= cutoff);
+        }
+    }
+    fn record_draw(&mut self, draw: Draw) {
+        self.state.history.push(draw);
+        if self.state.history.len() > HISTORY_LIMIT {
+            let excess = self.state.history.len() - HISTORY_LIMIT;
+            self.state.history.drain(..excess);
+        }
+    }
+    // Show the busy overlay, then run the import once it has had a chance to
+    // paint
+    fn start_import(&mut self, import: Import) {
+        self.pending_import = Some(import);
+        self.view.busy = true;
+        self._busy = Some(self.timeout.spawn(
+            Duration::from_millis(BUSY_DELAY_MS),
+            self.link.callback(|_| Msg::RunImport),
+        ));
+    }
+    fn import_outline(&mut self, text: &str) {
+        match outline::parse(text) {
+            Err(err) => self
+                .dialog
+                .alert(&format!("Couldn't import outline. {}", err)),
+            Ok(outline) => {
+                if self
+                    .dialog
+                    .confirm("Replace all saved lists and groups with this outline?")
+                {
+                    let lists = outline
+                        .lists
+                        .into_iter()
+                        .map(|(name, items)| {
+                            let items = items
+                                .into_iter()
+                                .map(|name| Item {
+                                    name: Some(name),
+                                    ..Default::default()
+                                })
+                                .collect();
+                            (name, items)
+                        })
+                        .collect();
+                    self.state = State {
+                        lists,
+                        groups: outline
+                            .groups
+                            .into_iter()
+                            .map(|(name, lists)| {
+                                (name, lists.into_iter().map(GroupEntry::List).collect())
+                            })
+                            .collect(),
+                        ..Default::default()
+                    };
+                    let current_list = self.state.lists.keys().next().cloned().unwrap_or_default();
+                    let current_group =
+                        self.state.groups.keys().next().cloned().unwrap_or_default();
+                    self.view = View::new(current_list, current_group);
+                }
+            }
+        }
+    }
+    // Add each bookmark to its folder's list, skipping links the list already
+    // has
+    fn import_bookmarks(&mut self, folders: Vec<(bookmarks::Folder, String)>) {
+        let mut added = 0;
+        let mut lists = BTreeSet::new();
+        for (folder, name) in folders {
+            if name.is_empty() {
+                continue;
+            }
+            if !self.state.lists.contains_key(&name) && !self.state.list_order.is_empty() {
+                self.state.list_order.push(name.clone());
+            }
+            let list = self.state.lists.entry(name.clone()).or_default();
+            for bookmark in folder.bookmarks {
+                if list
+                    .iter()
+                    .any(|item| item.link.as_ref() == Some(&bookmark.href))
+                {
+                    continue;
+                }
+                list.push(Item {
+                    name: match bookmark.title.is_empty() {
+                        true => None,
+                        false => Some(bookmark.title),
+                    },
+                    link: Some(bookmark.href),
+                    ..Default::default()
+                });
+                added += 1;
+                lists.insert(name.clone());
+            }
+        }
+        self.toast(format!(
+            "Added {} bookmarks to {} lists",
+            added,
+            lists.len()
+        ));
+    }
+    fn import_json(&mut self, text: &str) {
+        match serde_json::from_str(text) {
+            Err(err) => self.dialog.alert(&format!(
+                "Couldn't import data. This isn't valid JSON: {}",
+                err
+            )),
+            Ok(value) => {
+                let problems = import::problems(&value);
+                if !problems.is_empty() {
+                    let mut message = problems
+                        .iter()
+                        .take(IMPORT_PROBLEM_LIMIT)
+                        .fold("Couldn't import data:".to_owned(), |message, problem| {
+                            message + "\n" + problem
+                        });
+                    if problems.len() > IMPORT_PROBLEM_LIMIT {
+                        message +=
+                            &format!("\n...and {} more", problems.len() - IMPORT_PROBLEM_LIMIT);
+                    }
+                    self.dialog.alert(&message);
+                    return;
+                }
+                match serde_json::from_value::<State>(value) {
+                    Err(err) => self.dialog.alert(&format!("Couldn't import data. {}", err)),
+                    Ok(state) => {
+                        // An embedded board has nothing saved to replace
+                        if self.embed.is_some()
+                            || self
+                                .dialog
+                                .confirm("Replace all saved lists and groups with this data?")
+                        {
+                            self.view = View::for_state(&state);
+                            if let Some(group) = self.embed.as_ref() {
+                                self.view.current_group = group.clone();
+                            }
+                            self.state = state;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // Move an item from a list into the trash, returning a copy of it
+    fn trash_item(&mut self, name: String, idx: usize) -> Option<Item> {
+        let list = self.state.lists.get_mut(&name)?;
+        if idx >= list.len() {
+            return None;
+        }
+        let item = list.remove(idx);
+        self.item_removed(&name, idx);
+        self.state.trashed.push((name, item.clone()));
+        if self.state.trashed.len() > TRASH_LIMIT {
+            let excess = self.state.trashed.len() - TRASH_LIMIT;
+            self.state.trashed.drain(..excess);
+        }
+        Some(item)
+    }
+    // The opposite of item_removed, after an item is put back at `idx`
+    fn item_inserted(&mut self, name: &str, idx: usize) {
+        let shift = |current: usize| if current >= idx { current + 1 } else { current };
+        for drawn in self
+            .state
+            .list_settings
+            .get_mut(name)
+            .map(|settings| &mut settings.bag)
+            .into_iter()
+            .chain(self.view.temp_excluded.get_mut(name))
+        {
+            *drawn = drawn.iter().cloned().map(shift).collect();
+        }
+        if let Some(chosen) = self.view.cache.get_mut(name) {
+            chosen.idx = chosen.idx.map(shift);
+        }
+        if let Some((consumed, _)) = self.view.last_consumed.get_mut(name) {
+            *consumed = shift(*consumed);
+        }
+        if name == self.view.current_list {
+            self.view.selected_items = self
+                .view
+                .selected_items
+                .iter()
+                .cloned()
+                .map(shift)
+                .collect();
+            self.view.current_item = self.view.current_item.map(shift);
+        }
+    }
+    // Reorder a list's items that aren't position-locked with `arrange`, which
+    // gets their indices.  Each locked item keeps its own index, so locked
+    // items can't collide however many there are; the others fill the gaps
+    // between them in their new order.
+    fn rearrange(&mut self, name: &str, arrange: impl FnOnce(&mut Vec<usize>, &[Item])) {
+        let list = match self.state.lists.get_mut(name) {
+            Some(list) => list,
+            None => return,
+        };
+        let slots: Vec<usize> = (0..list.len())
+            .filter(|idx| !list[*idx].position_locked)
+            .collect();
+        let mut free = slots.clone();
+        arrange(&mut free, list);
+        // The old index of the item now at each index
+        let mut order: Vec<usize> = (0..list.len()).collect();
+        for (slot, old) in slots.into_iter().zip(free) {
+            order[slot] = old;
+        }
+        *list = order.iter().map(|old| list[*old].clone()).collect();
+        let moved = |idx: usize| order.iter().position(|old| *old == idx).unwrap_or(idx);
+        for drawn in self
+            .state
+            .list_settings
+            .get_mut(name)
+            .map(|settings| &mut settings.bag)
+            .into_iter()
+            .chain(self.view.temp_excluded.get_mut(name))
+        {
+            *drawn = drawn.iter().map(|idx| moved(*idx)).collect();
+        }
+        for chosen in self
+            .view
+            .cache
+            .get_mut(name)
+            .into_iter()
+            .chain(self.view.flash.get_mut(name))
+        {
+            chosen.idx = chosen.idx.map(moved);
+        }
+        // Undo Draw puts the consumed item back in front of the item that
+        // followed it, wherever that went
+        if let Some((idx, _)) = self.view.last_consumed.get_mut(name) {
+            *idx = moved(*idx);
+        }
+        if name == self.view.current_list {
+            self.view.selected_items = self
+                .view
+                .selected_items
+                .iter()
+                .map(|idx| moved(*idx))
+                .collect();
+            self.view.current_item = self.view.current_item.map(moved);
+        }
+    }
+    // Keep the focused and selected items pointing at the same entries after
+    // `idx` is removed from list `name`.
+    fn item_removed(&mut self, name: &str, idx: usize) {
+        for drawn in self
+            .state
+            .list_settings
+            .get_mut(name)
+            .map(|settings| &mut settings.bag)
+            .into_iter()
+            .chain(self.view.temp_excluded.get_mut(name))
+        {
+            *drawn = drawn
+                .iter()
+                .filter(|drawn| **drawn != idx)
+                .map(|drawn| if *drawn > idx { drawn - 1 } else { *drawn })
+                .collect();
+        }
+        if let Some(chosen) = self.view.cache.get_mut(name) {
+            chosen.idx = match chosen.idx {
+                Some(current) if current == idx => None,
+                Some(current) if current > idx => Some(current - 1),
+                current => current,
+            };
+        }
+        if let Some((consumed, _)) = self.view.last_consumed.get_mut(name) {
+            if *consumed > idx {
+                *consumed -= 1;
+            }
+        }
+        if name == self.view.current_list {
+            self.view.selected_items = self
+                .view
+                .selected_items
+                .iter()
+                .filter(|selected| **selected != idx)
+                .map(|selected| {
+                    if *selected > idx {
+                        selected - 1
+                    } else {
+                        *selected
+                    }
+                })
+                .collect();
+            self.view.current_item = match self.view.current_item {
+                Some(current) if current == idx => None,
+                Some(current) if current > idx => Some(current - 1),
+                current => current,
+            };
+        }
+    }
+    // What a pick from `name` is about to change
+    fn before_pick(&self, name: &str) -> Option<PickUndo> {
+        Some(PickUndo {
+            list: name.to_owned(),
+            time: js_sys::Date::now(),
+            items: self.state.lists.get(name)?.clone(),
+            after: String::new(),
+            previous: self.view.cache.get(name).cloned(),
+            revealed: self
+                .view
+                .revealed
+                .contains(&(self.view.current_group.clone(), name.to_owned())),
+            last_consumed: self.view.last_consumed.get(name).cloned(),
+            bag: self
+                .state
+                .list_settings
+                .get(name)
+                .map(|settings| settings.bag.clone())
+                .unwrap_or_default(),
+            thawed: Vec::new(),
+        })
+    }
+    // Whether the latest draw is from `name`, at or after `since`
+    fn drew_since(&self, name: &str, since: f64) -> bool {
+        match self.state.history.last() {
+            Some(draw) => draw.list == name && draw.time >= since,
+            None => false,
+        }
+    }
+    // Keep `undo` for Undo Pick if something was actually drawn
+    fn after_pick(&mut self, undo: Option<PickUndo>) {
+        let mut undo = match undo {
+            Some(undo) => undo,
+            None => return,
+        };
+        if !self.drew_since(&undo.list, undo.time) {
+            return;
+        }
+        if let Some(draw) = self.state.history.last() {
+            undo.time = draw.time;
+        }
+        undo.after = serde_json::to_string(&self.state.lists[&undo.list]).unwrap_or_default();
+        let stack = self
+            .view
+            .pick_undo
+            .entry(self.view.current_group.clone())
+            .or_default();
+        stack.push(undo);
+        if stack.len() > PICK_UNDO_LIMIT {
+            stack.remove(0);
+        }
+    }
+    fn undo_pick(&mut self, undo: PickUndo) {
+        let (name, time) = (undo.list, undo.time);
+        let now = self
+            .state
+            .lists
+            .get(&name)
+            .and_then(|list| serde_json::to_string(list).ok());
+        if now.as_ref() != Some(&undo.after) {
+            self.toast(format!(
+                "{} has changed since that pick, so it can't be taken back",
+                name
+            ));
+            return;
+        }
+        self.state.lists.insert(name.clone(), undo.items);
+        if let Some(idx) = self
             .state
+            .history
+            .iter()
+            .rposition(|draw| draw.list == name && draw.time == time)
+        {
+            self.state.history.remove(idx);
+        }
+        let key = (self.view.current_group.clone(), name.clone());
+        match undo.revealed {
+            true => self.view.revealed.insert(key),
+            false => self.view.revealed.remove(&key),
+        };
+        if let Some(settings) = self.state.list_settings.get_mut(&name) {
+            settings.bag = undo.bag;
+        }
+        match undo.previous {
+            Some(chosen) => self.view.cache.insert(name.clone(), chosen),
+            None => self.view.cache.remove(&name),
+        };
+        for (list, chosen) in undo.thawed {
+            self.view.cache.entry(list).or_insert(chosen);
+        }
+        match undo.last_consumed {
+            Some(consumed) => self.view.last_consumed.insert(name.clone(), consumed),
+            None => self.view.last_consumed.remove(&name),
+        };
+        self.view.celebration = None;
+        self.toast(format!("Took back the pick from {}", name));
+    }
+    // Put back the last item consumed from `name`, if there is one.
+    fn undo_draw(&mut self, name: &str) {
+        if let Some((idx, item)) = self.view.last_consumed.remove(name) {
+            if let Some(list) = self.state.lists.get_mut(name) {
+                let idx = idx.min(list.len());
+                list.insert(idx, item);
+                self.item_inserted(name, idx);
+            }
+        }
+    }
+    // The weights actually used when drawing from a list.  Anything that
+    // adjusts an item's odds belongs here so the list display matches.
+    fn effective_weights(&self, name: &str) -> Vec<f64> {
+        let list = match self.state.lists.get(name) {
+            Some(list) => list,
+            None => return Vec::new(),
+        };
+        let overrides = self.group_weights(name);
+        let weights: Vec<f64> = list
+            .iter()
+            .map(|item| match item.enabled {
+                true => item
+                    .name
+                    .as_ref()
+                    .and_then(|item| overrides?.get(item).map(|weight| weight.max(0.0)))
+                    .unwrap_or_else(|| self.item_weight(name, item)),
+                false => 0.0,
+            })
+            .collect();
+        // Leave out items not available today, unless that leaves nothing
+        let today = js_sys::Date::new_0().get_day();
+        let available: Vec<f64> = list
+            .iter()
+            .zip(weights.iter())
+            .map(|(item, weight)| match item.available_on(today) {
+                true => *weight,
+                false => 0.0,
+            })
+            .collect();
+        let weights = match available.iter().any(|weight| *weight > 0.0) {
+            true => available,
+            false => weights,
+        };
+        // Likewise items still cooling down
+        let ready: Vec<f64> = list
+            .iter()
+            .zip(weights.iter())
+            .map(|(item, weight)| match item.cooldown {
+                0 => *weight,
+                _ => 0.0,
+            })
+            .collect();
+        let weights = match ready.iter().any(|weight| *weight > 0.0) {
+            true => ready,
+            false => weights,
+        };
+        // Then whatever is skipped for this draw only
+        let excluded = match self.view.temp_excluded.get(name) {
+            Some(excluded) => excluded,
+            None => return weights,
+        };
+        let kept: Vec<f64> = weights
+            .iter()
+            .enumerate()
+            .map(|(idx, weight)| match excluded.contains(&idx) {
+                true => 0.0,
+                false => *weight,
+            })
+            .collect();
+        match kept.iter().any(|weight| *weight > 0.0) {
+            true => kept,
+            false => weights,
+        }
+    }
+    fn tiers(&self, name: &str) -> Vec<Tier> {
+        match self.state.list_settings.get(name) {
+            Some(settings) if !settings.tiers.is_empty() => settings.tiers.clone(),
+            _ => DEFAULT_TIERS
+                .iter()
+                .map(|(name, weight)| Tier {
+                    name: (*name).to_owned(),
+                    weight: *weight,
+                })
+                .collect(),
+        }
+    }
+    // Move group weight overrides from `old` to the new name of item `idx`
+    // in `list`, or copy them while another item there still goes by `old`
+    fn rename_weight_overrides(&mut self, list: &str, idx: usize, old: &str) {
+        let new = match self.state.lists.get(list).and_then(|items| items.get(idx)) {
+            Some(item) => item.name.clone(),
+            None => return,
+        };
+        let shared = self.state.lists[list]
+            .iter()
+            .any(|item| item.name.as_deref() == Some(old));
+        for settings in self.state.group_settings.values_mut() {
+            let weights = match settings.weights.get_mut(list) {
+                Some(weights) => weights,
+                None => continue,
+            };
+            let weight = match shared {
+                true => weights.get(old).copied(),
+                false => weights.remove(old),
+            };
+            if let (Some(weight), Some(new)) = (weight, new.clone()) {
+                weights.entry(new).or_insert(weight);
+            }
+        }
+    }
+    // The current group's weight overrides for a list in it
+    fn group_weights(&self, list: &str) -> Option<&BTreeMap<String, f64>> {
+        let group = &self.view.current_group;
+        let weights = self.state.group_settings.get(group)?.weights.get(list)?;
+        match self.group_lists(group).iter().any(|name| name == list) {
+            true => Some(weights),
+            false => None,
+        }
+    }
+    // An item's weight before it is enabled or filtered, from its tier if it
+    // has one the list still knows about
+    fn item_weight(&self, list: &str, item: &Item) -> f64 {
+        item.tier
+            .as_ref()
+            .and_then(|tier| self.tiers(list).into_iter().find(|t| t.name == *tier))
+            .map(|tier| tier.weight.max(0.0))
+            .unwrap_or_else(|| item.base_weight())
+    }
+    // The weights the draw uses for the list's enabled items
+    fn enabled_weights(&self, name: &str) -> Vec<f64> {
+        self.state
             .lists
             .get(name)
-            .map(|list| list.iter().choose(&mut rng).unwrap().to_owned())
-            .unwrap_or_default();
-        item
+            .into_iter()
+            .flatten()
+            .filter(|item| item.enabled)
+            .map(|item| self.item_weight(name, item))
+            .collect()
+    }
+    // Run every weight the draw reads for `name` through `reweigh`: each
+    // tier's, which stands in for its items' own, and the rest of the items'
+    // own.  None leaves an item at the default weight.
+    fn reweigh(&mut self, name: &str, reweigh: impl Fn(f64) -> Option<f64>) {
+        let mut tiers = self.tiers(name);
+        let list = match self.state.lists.get_mut(name) {
+            Some(list) => list,
+            None => return,
+        };
+        let mut tiered = false;
+        for item in list.iter_mut() {
+            match item.tier.as_ref() {
+                Some(tier) if tiers.iter().any(|t| t.name == *tier) => tiered = true,
+                _ => item.weight = reweigh(item.base_weight()),
+            }
+        }
+        if tiered {
+            for tier in tiers.iter_mut() {
+                tier.weight = reweigh(tier.weight).unwrap_or(1.0);
+            }
+            self.state
+                .list_settings
+                .entry(name.to_owned())
+                .or_default()
+                .tiers = tiers;
+        }
+    }
+    fn cooldown(&self, name: &str) -> usize {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.cooldown)
+            .unwrap_or_default()
+    }
+    fn required(&self, name: &str) -> Option<Required> {
+        self.state.list_settings.get(name)?.required
+    }
+    fn warns_missing(&self, name: &str) -> bool {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.warn_missing)
+            .unwrap_or_default()
+    }
+    // Items in the list without its required field
+    fn missing_count(&self, name: &str) -> usize {
+        match (self.required(name), self.state.lists.get(name)) {
+            (Some(required), Some(list)) => {
+                list.iter().filter(|item| required.missing(item)).count()
+            }
+            _ => 0,
+        }
+    }
+    // False if the list warns about missing fields and drawing was called off
+    fn confirm_missing(&mut self, name: &str) -> bool {
+        let required = match self.required(name) {
+            Some(required) if self.warns_missing(name) => required,
+            _ => return true,
+        };
+        match self.missing_count(name) {
+            0 => true,
+            missing => self.dialog.confirm(&format!(
+                "{} items in {} have no {}. Draw anyway?",
+                missing,
+                name,
+                required.label()
+            )),
+        }
+    }
+    fn template(&self, name: &str) -> Option<&str> {
+        self.state.list_settings.get(name)?.template.as_deref()
+    }
+    // Whether the list's pick is pinned.  Every group showing the list shares
+    // its pick, so a pin in any of them holds it.
+    fn pick_pinned(&self, name: &str) -> bool {
+        self.view.pinned_picks.iter().any(|(_, list)| list == name)
+    }
+    // The pinned list whose pick `msg` would replace or clear, if any
+    fn pinned_target(&self, msg: &Msg) -> Option<String> {
+        let list = match msg {
+            Msg::RerollList(name)
+            | Msg::ThawList(name)
+            | Msg::ExcludeChosen(name)
+            | Msg::UndoDraw(name) => name,
+            Msg::ShowRecent(idx) => &self.state.history.get(*idx)?.list,
+            Msg::UndoPick => {
+                &self
+                    .view
+                    .pick_undo
+                    .get(&self.view.current_group)?
+                    .last()?
+                    .list
+            }
+            _ => return None,
+        };
+        Some(list.clone()).filter(|list| self.pick_pinned(list))
+    }
+    // Forget pins on lists whose pick has gone, so the next pick isn't
+    // pinned from the start
+    fn drop_stale_pins(&mut self) {
+        let cache = &self.view.cache;
+        self.view
+            .pinned_picks
+            .retain(|(_, list)| cache.contains_key(list));
+    }
+    fn draw_style(&self, name: &str) -> DrawStyle {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.draw_style)
+            .unwrap_or_default()
+    }
+    // The classes animating `name`'s pick in the group: a looping one while
+    // it spins and a one-off as it freezes
+    fn draw_style_class(&self, name: &str, frozen: bool) -> String {
+        let style = self.draw_style(name);
+        if style == DrawStyle::Flash || prefers_reduced_motion() {
+            return String::new();
+        }
+        match self.view.revealing.get(name) {
+            Some(_) if frozen => format!("{} revealing", style.class()),
+            None if !frozen => format!("{} spinning", style.class()),
+            _ => String::new(),
+        }
+    }
+    fn on_empty(&self, name: &str) -> EmptyPolicy {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.on_empty)
+            .unwrap_or_default()
+    }
+    fn numeric(&self, name: &str) -> Option<NumericList> {
+        self.state.list_settings.get(name)?.numeric
+    }
+    fn uses_tickets(&self, name: &str) -> bool {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.tickets)
+            .unwrap_or_default()
+    }
+    fn uses_percentages(&self, name: &str) -> bool {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.percentages)
+            .unwrap_or_default()
+    }
+    fn draw_mode(&self, name: &str) -> DrawMode {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.draw_mode)
+            .unwrap_or_default()
+    }
+    // Start a new cycle once a bag-mode list has drawn everything it can
+    fn refill_bag(&mut self, name: &str) {
+        if self.draw_mode(name) != DrawMode::Bag {
+            return;
+        }
+        let weights = self.effective_weights(name);
+        if let Some(drawn) = self
+            .state
+            .list_settings
+            .get_mut(name)
+            .map(|settings| &mut settings.bag)
+        {
+            let exhausted = weights
+                .iter()
+                .enumerate()
+                .all(|(idx, weight)| *weight <= 0.0 || drawn.contains(&idx));
+            if exhausted {
+                drawn.clear();
+            }
+        }
+    }
+    fn in_rotation(&self, name: &str) -> bool {
+        self.state
+            .list_settings
+            .get(name)
+            .map(|settings| settings.rotation)
+            .unwrap_or_default()
+    }
+    // How likely each item in a list is to be drawn next, and the name of the
+    // rule that decided it.  Rotation only gives odds to the items chosen
+    // longest ago, with never-chosen items first.
+    fn draw_weights(&self, name: &str) -> (&'static str, Vec<f64>) {
+        let list = match self.state.lists.get(name) {
+            Some(list) => list,
+            None => return ("weighted", Vec::new()),
+        };
+        let weights = self.effective_weights(name);
+        if !self.in_rotation(name) {
+            let even = |weight: f64| match weight > 0.0 {
+                true => 1.0,
+                false => 0.0,
+            };
+            return match self.draw_mode(name) {
+                DrawMode::Weighted => ("weighted", weights),
+                DrawMode::Uniform => ("uniform", weights.iter().map(|w| even(*w)).collect()),
+                // Drawing evenly from what is left in the bag is the same as
+                // drawing through a shuffle of it, and stays verifiable
+                DrawMode::Bag => {
+                    let drawn = self
+                        .state
+                        .list_settings
+                        .get(name)
+                        .map(|settings| &settings.bag);
+                    let left = weights.iter().enumerate().map(|(idx, weight)| {
+                        match drawn.map(|drawn| drawn.contains(&idx)).unwrap_or(false) {
+                            true => 0.0,
+                            false => even(*weight),
+                        }
+                    });
+                    ("bag", left.collect())
+                }
+            };
+        }
+        let last_chosen = |idx: usize| list[idx].last_chosen.unwrap_or(f64::NEG_INFINITY);
+        let oldest = (0..list.len())
+            .filter(|idx| weights[*idx] > 0.0)
+            .map(last_chosen)
+            .fold(f64::INFINITY, f64::min);
+        let weights = weights
+            .iter()
+            .enumerate()
+            .map(
+                |(idx, weight)| match *weight > 0.0 && last_chosen(idx) == oldest {
+                    true => 1.0,
+                    false => 0.0,
+                },
+            )
+            .collect();
+        ("least recently chosen", weights)
+    }
+    // Each item's chance of being the next draw, from 0 to 1
+    fn list_probabilities(&self, name: &str) -> Vec<f64> {
+        let (_, weights) = self.draw_weights(name);
+        let total: f64 = weights.iter().sum();
+        weights
+            .iter()
+            .map(|weight| match total > 0.0 {
+                true => weight / total,
+                false => 0.0,
+            })
+            .collect()
+    }
+    // Every item, those that could be drawn in the order repeated draws
+    // without replacement would pick them, then the rest in list order
+    fn weighted_order(&self, name: &str) -> Vec<Item> {
+        let list = match self.state.lists.get(name) {
+            Some(list) => list,
+            None => return vec![],
+        };
+        let mut weights = self.effective_weights(name);
+        let mut rng = self.rng.borrow_mut();
+        let mut ranked = vec![];
+        let mut left = vec![true; list.len()];
+        while let Some(idx) = weighted_index(&weights, &mut *rng) {
+            ranked.push(list[idx].clone());
+            weights[idx] = 0.0;
+            left[idx] = false;
+        }
+        ranked.extend(
+            list.iter()
+                .zip(left)
+                .filter(|(_, left)| *left)
+                .map(|(item, _)| item.clone()),
+        );
+        ranked
+    }
+    // A randomly chosen item, weighted by effective weight, or a number for a
+    // numeric list
+    fn choose_from_list(&self, name: &str) -> Option<Chosen> {
+        if let Some(numeric) = self.numeric(name) {
+            let item = numeric.roll(&mut *self.rng.borrow_mut())?;
+            return Some(Chosen { item, idx: None });
+        }
+        let (_, weights) = self.draw_weights(name);
+        let idx = weighted_index(&weights, &mut *self.rng.borrow_mut())?;
+        Some(Chosen {
+            item: self.state.lists[name][idx].clone(),
+            idx: Some(idx),
+        })
+    }
+}
+
+// Pick an index with probability proportional to its weight.  Draw proofs
+// depend on this consuming the RNG the same way every time.
+fn weighted_index(weights: &[f64], rng: &mut impl Rng) -> Option<usize> {
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut target = rng.gen::<f64>() * total;
+    for (idx, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return Some(idx);
+        }
+        target -= weight;
+    }
+    // Floating point rounding can leave a sliver past the last item
+    weights.iter().rposition(|weight| *weight > 0.0)
+}
+
+// What the undo toast calls a deletion, and the groups it took an emptied list
+// out of
+fn deleted_label(label: String, list: &str, memberships: &[(String, usize)]) -> String {
+    match memberships.len() {
+        0 => label,
+        1 => format!("{}, and took {} out of its group", label, list),
+        groups => format!("{}, and took {} out of {} groups", label, list, groups),
+    }
+}
+
+// "just now", "1 minute ago", "3 days ago", ...
+fn format_relative(ms_ago: f64) -> String {
+    let seconds = (ms_ago / 1000.0).max(0.0) as u64;
+    let (count, unit) = match seconds {
+        0..=59 => return "just now".to_owned(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86_399 => (seconds / 3600, "hour"),
+        86_400..=2_591_999 => (seconds / 86_400, "day"),
+        2_592_000..=31_535_999 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    match count {
+        1 => format!("1 {} ago", unit),
+        count => format!("{} {}s ago", count, unit),
+    }
+}
+
+// A sum of item values to three decimal places, so 0.1 + 0.2 shows, and
+// fits a cap of, 0.3
+fn round_value(value: f64) -> f64 {
+    // Adding 0 turns a rounded -0 into 0
+    (value * 1000.0).round() / 1000.0 + 0.0
+}
+
+// A small board for first-time users: two lists and a group drawing from both
+fn sample_state() -> State {
+    let items = |names: &[&str]| -> Vec<Item> {
+        names
+            .iter()
+            .map(|name| Item {
+                name: Some((*name).to_owned()),
+                ..Default::default()
+            })
+            .collect()
+    };
+    let mut lists = BTreeMap::new();
+    lists.insert(
+        "Movies".to_owned(),
+        items(&[
+            "The Princess Bride",
+            "Spirited Away",
+            "Paddington 2",
+            "Casablanca",
+        ]),
+    );
+    lists.insert(
+        "Restaurants".to_owned(),
+        items(&["Thai", "Pizza", "Sushi", "Tacos", "Dumplings"]),
+    );
+    let mut groups = BTreeMap::new();
+    groups.insert(
+        "Date Night".to_owned(),
+        vec![
+            GroupEntry::List("Restaurants".to_owned()),
+            GroupEntry::List("Movies".to_owned()),
+        ],
+    );
+    State {
+        lists,
+        groups,
+        current_group: Some("Date Night".to_owned()),
+        ..Default::default()
+    }
+}
+
+// Ready-made lists a new list can start from
+#[derive(Clone, Copy, PartialEq)]
+pub enum TemplateId {
+    D20,
+    TwoD6,
+    YesNo,
+    Magic8Ball,
+    RockPaperScissors,
+}
+
+const LIST_TEMPLATES: [(TemplateId, &str); 5] = [
+    (TemplateId::D20, "Dice d20"),
+    (TemplateId::TwoD6, "Dice 2d6"),
+    (TemplateId::YesNo, "Yes/No"),
+    (TemplateId::Magic8Ball, "Magic 8-Ball"),
+    (TemplateId::RockPaperScissors, "Rock Paper Scissors"),
+];
+
+fn template_items(id: TemplateId) -> Vec<Item> {
+    let item = |name: String, weight: Option<f64>| Item {
+        name: Some(name),
+        weight,
+        ..Default::default()
+    };
+    let plain = |names: &[&str]| -> Vec<Item> {
+        names
+            .iter()
+            .map(|name| item((*name).to_owned(), None))
+            .collect()
+    };
+    match id {
+        TemplateId::D20 => (1..=20).map(|n| item(n.to_string(), None)).collect(),
+        // Each total is weighted by how many ways two dice can make it
+        TemplateId::TwoD6 => (2..=12)
+            .map(|total: i32| item(total.to_string(), Some((6 - (total - 7).abs()) as f64)))
+            .collect(),
+        TemplateId::YesNo => plain(&["Yes", "No"]),
+        TemplateId::Magic8Ball => plain(&[
+            "It is certain",
+            "It is decidedly so",
+            "Without a doubt",
+            "Yes definitely",
+            "You may rely on it",
+            "As I see it, yes",
+            "Most likely",
+            "Outlook good",
+            "Yes",
+            "Signs point to yes",
+            "Reply hazy, try again",
+            "Ask again later",
+            "Better not tell you now",
+            "Cannot predict now",
+            "Concentrate and ask again",
+            "Don't count on it",
+            "My reply is no",
+            "My sources say no",
+            "Outlook not so good",
+            "Very doubtful",
+        ]),
+        TemplateId::RockPaperScissors => plain(&["Rock", "Paper", "Scissors"]),
+    }
+}
+
+// Leave out every field the way loading would fill it in anyway.  `lists`
+// and `groups` stay, as an import needs them even when empty.
+fn strip_defaults(board: &mut serde_json::Value) {
+    fn defaults<T: Default + serde::Serialize>() -> serde_json::Value {
+        serde_json::to_value(T::default()).unwrap_or_default()
+    }
+    fn strip(value: &mut serde_json::Value, defaults: &serde_json::Value) {
+        if let (Some(object), Some(defaults)) = (value.as_object_mut(), defaults.as_object()) {
+            object.retain(|key, value| defaults.get(key) != Some(value));
+        }
+    }
+    let (item, list, group) = (
+        defaults::<Item>(),
+        defaults::<ListSettings>(),
+        defaults::<GroupSettings>(),
+    );
+    let state = serde_json::from_str::<State>(r#"{"lists": {}, "groups": {}}"#)
+        .ok()
+        .and_then(|state| serde_json::to_value(state).ok())
+        .unwrap_or_default();
+    if let Some(root) = board.as_object_mut() {
+        root.retain(|key, value| {
+            key == "lists" || key == "groups" || state.get(key) != Some(value)
+        });
+    }
+    for items in board["lists"]
+        .as_object_mut()
+        .into_iter()
+        .flat_map(|lists| lists.values_mut())
+    {
+        for value in items.as_array_mut().into_iter().flatten() {
+            strip(value, &item);
+        }
+    }
+    for (key, defaults) in [("list_settings", &list), ("group_settings", &group)] {
+        for value in board
+            .get_mut(key)
+            .and_then(|settings| settings.as_object_mut())
+            .into_iter()
+            .flat_map(|settings| settings.values_mut())
+        {
+            strip(value, defaults);
+        }
+    }
+}
+
+// Start fetching a shared board.  Failing to even send the request is
+// reported straight away as a BoardFetched error.
+fn fetch_board(
+    fetch: &mut FetchService,
+    link: &ComponentLink<App>,
+    url: String,
+) -> Option<FetchTask> {
+    let request = match Request::get(url.as_str()).body(Nothing) {
+        Ok(request) => request,
+        Err(err) => {
+            link.send_message(Msg::BoardFetched {
+                url,
+                board: Err(err.to_string()),
+            });
+            return None;
+        }
+    };
+    let url2 = url.clone();
+    let callback = link.callback(move |response: Response<Text>| {
+        let (meta, body) = response.into_parts();
+        let board = match body {
+            Ok(body) if meta.status.is_success() => Ok(body),
+            // yew reports a request the browser refused as a 408 with no body
+            Err(err) if meta.status.as_u16() == 408 => Err(format!(
+                "The server may be unreachable, or may not allow other sites to load it (CORS). ({})",
+                err
+            )),
+            Err(err) if meta.status.is_success() => Err(err.to_string()),
+            _ => Err(format!("The server answered {}.", meta.status)),
+        };
+        Msg::BoardFetched {
+            url: url2.clone(),
+            board,
+        }
+    });
+    match fetch.fetch(request, callback) {
+        Ok(task) => Some(task),
+        Err(err) => {
+            link.send_message(Msg::BoardFetched {
+                url,
+                board: Err(err.to_string()),
+            });
+            None
+        }
+    }
+}
+
+// A list as text for spotting edits, leaving out what drawing changes
+fn list_snapshot(items: &[Item]) -> String {
+    let items: Vec<Item> = items
+        .iter()
+        .map(|item| Item {
+            last_chosen: None,
+            cooldown: 0,
+            ..item.clone()
+        })
+        .collect();
+    serde_json::to_string(&items).unwrap_or_default()
+}
+
+// The enabled controls inside an element, in tab order
+fn focusable_in(element: &Element) -> Vec<HtmlElement> {
+    let nodes = match element.query_selector_all("a[href], button, input, select, textarea") {
+        Ok(nodes) => nodes,
+        Err(_) => return Vec::new(),
+    };
+    (0..nodes.length())
+        .filter_map(|idx| nodes.get(idx))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .filter(|element| !element.has_attribute("disabled"))
+        .collect()
+}
+
+// Whether the focused element is a text field
+fn editing_text() -> bool {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.active_element())
+        .map(|el| {
+            let tag = el.tag_name();
+            tag == "INPUT" || tag == "TEXTAREA"
+        })
+        .unwrap_or(false)
+}
+
+fn prefers_reduced_motion() -> bool {
+    web_sys::window()
+        .and_then(|window| window.match_media("(prefers-reduced-motion: reduce)").ok())
+        .flatten()
+        .map(|query| query.matches())
+        .unwrap_or(false)
+}
+
+// Whether a touch began on a field or on something that scrolls sideways,
+// either of which needs the gesture more than the tabs do
+fn starts_on_scrollable(e: &TouchEvent) -> bool {
+    let mut el = e
+        .target()
+        .and_then(|target| target.dyn_into::<Element>().ok());
+    while let Some(current) = el {
+        if current
+            .class_name()
+            .split_whitespace()
+            .any(|class| class == "autospoon")
+        {
+            return false;
+        }
+        let tag = current.tag_name();
+        if tag == "INPUT" || tag == "TEXTAREA" || current.scroll_width() > current.client_width() {
+            return true;
+        }
+        el = current.parent_element();
+    }
+    false
+}
+
+// Every non-overlapping occurrence of `find` in `text` replaced, left to right
+fn replace_matches(text: &str, find: &str, with: &str, ignore_case: bool) -> String {
+    if !ignore_case {
+        return text.replace(find, with);
+    }
+    let find: Vec<char> = find.chars().collect();
+    let chars: Vec<char> = text.chars().collect();
+    let mut replaced = String::with_capacity(text.len());
+    let mut idx = 0;
+    while idx < chars.len() {
+        let found = chars.len() - idx >= find.len()
+            && find
+                .iter()
+                .zip(&chars[idx..])
+                .all(|(f, c)| f.to_lowercase().eq(c.to_lowercase()));
+        if found {
+            replaced.push_str(with);
+            idx += find.len();
+        } else {
+            replaced.push(chars[idx]);
+            idx += 1;
+        }
+    }
+    replaced
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// A single class name: letters, digits, '-' and '_' only, so it can't add
+// attributes or other classes
+fn sanitize_class(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
+
+// Text that can't break out of a Markdown table cell
+fn markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+// "a, b,, c " as ["a", "b", "c"]
+fn comma_separated(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn item_label(name: &Option<String>) -> String {
+    name.clone().unwrap_or_else(|| "(unnamed)".to_owned())
+}
+
+// Keys of `entries` in the given order, then any missing from it alphabetically
+fn ordered<'a, T>(order: &'a [String], entries: &'a BTreeMap<String, T>) -> Vec<&'a String> {
+    let mut keys: Vec<&String> = order
+        .iter()
+        .filter(|name| entries.contains_key(*name))
+        .collect();
+    keys.extend(entries.keys().filter(|name| !order.contains(name)));
+    keys
+}
+
+// Move `dragged` to just before `target`
+fn move_before(mut order: Vec<String>, dragged: &str, target: &str) -> Vec<String> {
+    if dragged == target {
+        return order;
+    }
+    if let Some(from) = order.iter().position(|x| x == dragged) {
+        let moved = order.remove(from);
+        let to = order
+            .iter()
+            .position(|x| x == target)
+            .unwrap_or(order.len());
+        order.insert(to, moved);
+    }
+    order
+}
+
+fn board_key(board: &str) -> String {
+    if board.is_empty() {
+        KEY.to_owned()
+    } else {
+        format!("{}.{}", KEY, board)
+    }
+}
+
+// The file name in a URL without its extension, e.g. "sunset" for
+// https://example.com/photos/sunset.jpg?w=400
+fn name_from_url(url: &str) -> Option<String> {
+    if url.starts_with("data:") {
+        return None;
+    }
+    let path = url.split(['?', '#']).next()?;
+    let file = path.trim_end_matches('/').rsplit('/').next()?;
+    let stem = match file.rfind('.') {
+        Some(dot) if dot > 0 => &file[..dot],
+        _ => file,
+    };
+    // Escapes that don't decode, like a stray %, stay as they are
+    let name = js_sys::decode_uri_component(stem)
+        .map(String::from)
+        .unwrap_or_else(|_| stem.to_owned());
+    if name.is_empty() || file.contains(':') {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn is_large_image(image: &str) -> bool {
+    image.starts_with("data:") && image.len() > LARGE_IMAGE_BYTES
+}
+
+fn load_state(storage: &StorageService, board: &str) -> State {
+    let mut state = if let Json(Ok(restored_entries)) = storage.restore(&board_key(board)) {
+        restored_entries
+    } else {
+        State::default()
+    };
+    for fix in state.repair() {
+        warn!("Repaired saved board: {}", fix);
+    }
+    state
+}
+
+// Pick up a saved selection if it still exists.  Nothing saved, or a list or
+// group that has since been deleted, falls back to the first one.
+fn restore_selection<T>(saved: &Option<String>, entries: &BTreeMap<String, T>) -> String {
+    match saved {
+        Some(name) if name.is_empty() || entries.contains_key(name) => name.clone(),
+        _ => entries.keys().next().cloned().unwrap_or_default(),
+    }
+}
+
+// Inline style for a group background, which is either an image URL or any
+// CSS color.  Characters that could end the value early are dropped.
+fn background_style(background: &str) -> String {
+    let clean = |extra: char| -> String {
+        background
+            .chars()
+            .filter(|c| !matches!(c, '"' | '\\') && *c != extra && !c.is_control())
+            .collect()
+    };
+    if looks_like_url(background) {
+        format!(
+            "background-image: url(\"{}\"); background-size: cover;",
+            clean('"')
+        )
+    } else {
+        format!("background-color: {};", clean(';'))
+    }
+}
+
+// A URL has a scheme, like https: or data:, or is a path from / or .
+// Colors such as rgb(0 0 0 / 50%) can contain a slash too, but never a colon.
+fn looks_like_url(value: &str) -> bool {
+    let value = value.trim();
+    if value.starts_with('/') || value.starts_with('.') {
+        return true;
+    }
+    match value.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+// Wrap the characters at `positions` in <mark> tags
+fn render_highlighted(text: &str, positions: &[usize]) -> Html {
+    let mut runs: Vec<(bool, String)> = vec![];
+    for (idx, c) in text.chars().enumerate() {
+        let matched = positions.contains(&idx);
+        match runs.last_mut() {
+            Some((last, run)) if *last == matched => run.push(c),
+            _ => runs.push((matched, c.to_string())),
+        }
+    }
+    html! {
+        <span class="match">
+        {for runs.into_iter().map(|(matched, run)| if matched {
+            html! {<mark>{run}</mark>}
+        } else {
+            html! {{run}}
+        })}
+        </span>
+    }
+}
+
+// name_from_url decodes with js_sys, which needs a JavaScript engine, so
+// these only run under wasm-pack test
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::*;
+
+    #[wasm_bindgen_test]
+    fn names_an_item_after_its_url() {
+        assert_eq!(
+            name_from_url("https://example.com/photos/sunset.jpg?w=400").as_deref(),
+            Some("sunset")
+        );
+        assert_eq!(
+            name_from_url("https://example.com/caf%C3%A9%20au%20lait.png").as_deref(),
+            Some("café au lait")
+        );
+        assert_eq!(
+            name_from_url("https://example.com/100%.png").as_deref(),
+            Some("100%")
+        );
+        assert_eq!(name_from_url("data:image/png;base64,AAAA"), None);
+        assert_eq!(name_from_url("mailto:someone@example.com"), None);
     }
 }
@@ -0,0 +1,173 @@
+//! Reads the bookmarks.html browsers export, in the old Netscape format.
+//! Folders are `<DT><H3>` headings each followed by a `<DL>` of what is in
+//! them, and bookmarks are `<DT><A HREF="...">` links.  Like page.rs this
+//! searches for those tags rather than parsing HTML properly.
+
+use crate::page;
+
+pub struct Folder {
+    // From the top folder down, e.g. "Bookmarks / Bookmarks bar / Reading"
+    pub name: String,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+pub struct Bookmark {
+    pub title: String,
+    pub href: String,
+}
+
+// Every folder with bookmarks directly in it, in the order they first appear
+pub fn parse(html: &str) -> Vec<Folder> {
+    let lower = html.to_ascii_lowercase();
+    let mut folders: Vec<Folder> = vec![];
+    // Names of the folders the current <DL> is nested in
+    let mut path: Vec<String> = vec![];
+    // The heading just seen, waiting for its <DL>
+    let mut heading: Option<String> = None;
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find('<') {
+        let start = rest + found;
+        let end = match lower[start..].find('>') {
+            Some(end) => start + end + 1,
+            None => break,
+        };
+        rest = end;
+        let tag = &lower[start..end];
+        if tag.starts_with("<h1") || tag.starts_with("<h3") {
+            let close = if tag.starts_with("<h1") {
+                "</h1"
+            } else {
+                "</h3"
+            };
+            heading = lower[end..]
+                .find(close)
+                .map(|len| page::decode_entities(html[end..end + len].trim()));
+        } else if tag.starts_with("<dl") {
+            path.push(heading.take().unwrap_or_default());
+        } else if tag.starts_with("</dl") {
+            path.pop();
+        } else if tag.starts_with("<a ") {
+            let href = match page::attribute(&html[start..end], "href") {
+                Some(href) => page::decode_entities(&href),
+                None => continue,
+            };
+            // Browser-generated queries and bookmarklets, not pages
+            if href.starts_with("place:") || href.starts_with("javascript:") {
+                continue;
+            }
+            let title = lower[end..]
+                .find("</a")
+                .map(|len| page::decode_entities(html[end..end + len].trim()))
+                .unwrap_or_default();
+            let name = path
+                .iter()
+                .filter(|name| !name.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" / ");
+            let name = match name.is_empty() {
+                true => "Bookmarks".to_owned(),
+                false => name,
+            };
+            let bookmark = Bookmark { title, href };
+            match folders.iter_mut().find(|folder| folder.name == name) {
+                Some(folder) => folder.bookmarks.push(bookmark),
+                None => folders.push(Folder {
+                    name,
+                    bookmarks: vec![bookmark],
+                }),
+            }
+        }
+    }
+    folders
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Trimmed from a Firefox export
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks Menu</H1>
+<DL><p>
+    <DT><A HREF="place:type=6&amp;sort=14&amp;maxResults=10">Recent Tags</A>
+    <DT><A HREF="https://example.com/top">Top</A>
+    <DT><H3 ADD_DATE="1600000000">Reading</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/a?x=1&amp;y=2" ADD_DATE="1600000001">Fish &amp; Chips</A>
+        <DT><A HREF="javascript:alert(1)">Bookmarklet</A>
+        <DT><H3>Later</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/b">&lt;b&gt; isn&#39;t bold</A>
+        </DL><p>
+        <DT><A HREF="https://example.com/c">After Later</A>
+    </DL><p>
+</DL><p>
+"#;
+
+    fn summary(folders: &[Folder]) -> Vec<(&str, Vec<(&str, &str)>)> {
+        folders
+            .iter()
+            .map(|folder| {
+                let bookmarks = folder
+                    .bookmarks
+                    .iter()
+                    .map(|bookmark| (bookmark.title.as_str(), bookmark.href.as_str()))
+                    .collect();
+                (folder.name.as_str(), bookmarks)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested_folders() {
+        assert_eq!(
+            summary(&parse(EXPORT)),
+            vec![
+                ("Bookmarks Menu", vec![("Top", "https://example.com/top")]),
+                (
+                    "Bookmarks Menu / Reading",
+                    vec![
+                        ("Fish & Chips", "https://example.com/a?x=1&y=2"),
+                        ("After Later", "https://example.com/c"),
+                    ]
+                ),
+                (
+                    "Bookmarks Menu / Reading / Later",
+                    vec![("<b> isn't bold", "https://example.com/b")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_queries_and_bookmarklets() {
+        let hrefs: Vec<String> = parse(EXPORT)
+            .into_iter()
+            .flat_map(|folder| folder.bookmarks)
+            .map(|bookmark| bookmark.href)
+            .collect();
+        assert!(hrefs
+            .iter()
+            .all(|href| !href.starts_with("place:") && !href.starts_with("javascript:")));
+        assert_eq!(hrefs.len(), 4);
+    }
+
+    #[test]
+    fn loose_bookmarks() {
+        // No heading names the top <DL>
+        let html = r#"<DL><DT><a href='https://example.com'>Example</a></DL>"#;
+        assert_eq!(
+            summary(&parse(html)),
+            vec![("Bookmarks", vec![("Example", "https://example.com")])]
+        );
+    }
+
+    #[test]
+    fn empty_folders_are_left_out() {
+        let html = "<H1>Menu</H1><DL><DT><H3>Empty</H3><DL></DL></DL>";
+        assert!(parse(html).is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! A small subsequence matcher for the item search.
+//!
+//! Every character of the query has to appear in the candidate, in order but
+//! not necessarily adjacent.  Matches score higher when they are consecutive,
+//! start a word, or come early in the candidate, so "mvs" ranks "Movies" above
+//! "My Favourite Videos".
+
+pub struct Match {
+    pub score: i32,
+    // Char (not byte) positions in the candidate that matched the query
+    pub positions: Vec<usize>,
+}
+
+pub fn score(query: &str, candidate: &str) -> Option<Match> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return None;
+    }
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut wanted = query.iter().peekable();
+    let mut prev: Option<char> = None;
+    for (idx, c) in candidate.chars().enumerate() {
+        let next = match wanted.peek() {
+            Some(next) => **next,
+            None => break,
+        };
+        if c.to_lowercase().eq(std::iter::once(next)) {
+            score += 10;
+            if positions
+                .last()
+                .map(|last| last + 1 == idx)
+                .unwrap_or(false)
+            {
+                score += 15;
+            }
+            if prev.map(|p| !p.is_alphanumeric()).unwrap_or(true) {
+                score += 10;
+            }
+            // Earlier matches are worth a little more
+            score -= (idx as i32).min(10);
+            positions.push(idx);
+            wanted.next();
+        }
+        prev = Some(c);
+    }
+    if wanted.peek().is_some() {
+        return None;
+    }
+    Some(Match { score, positions })
+}
+
+// The better of the name's match and the best alias's, with the alias if it
+// was one.  A tie goes to the name.
+pub fn score_aliased<'a>(
+    query: &str,
+    name: &str,
+    aliases: &'a [String],
+) -> Option<(Match, Option<&'a str>)> {
+    let by_name = score(query, name).map(|found| (found, None));
+    let by_alias = aliases
+        .iter()
+        .filter_map(|alias| score(query, alias).map(|found| (found, Some(alias.as_str()))));
+    by_name
+        .into_iter()
+        .chain(by_alias)
+        .max_by_key(|(found, alias)| (found.score, alias.is_none()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_in_order_only() {
+        assert_eq!(score("mvs", "Movies").unwrap().positions, vec![0, 2, 5]);
+        assert!(score("svm", "Movies").is_none());
+        assert!(score("moviess", "Movies").is_none());
+    }
+
+    #[test]
+    fn ignores_case_and_spaces() {
+        assert_eq!(score("t P", "Thai Palace").unwrap().positions, vec![0, 5]);
+    }
+
+    #[test]
+    fn empty_query_matches_nothing() {
+        assert!(score("", "Movies").is_none());
+        assert!(score("  ", "Movies").is_none());
+    }
+
+    #[test]
+    fn ranks_runs_and_word_starts_higher() {
+        let movies = score("mvs", "Movies").unwrap().score;
+        let videos = score("mvs", "My Favourite Videos").unwrap().score;
+        assert!(movies > videos, "{} <= {}", movies, videos);
+        let run = score("hea", "Heat").unwrap().score;
+        let scattered = score("hea", "Harbour Eats").unwrap().score;
+        assert!(run > scattered, "{} <= {}", run, scattered);
+    }
+
+    #[test]
+    fn positions_count_chars() {
+        assert_eq!(score("ca", "Café au lait").unwrap().positions, vec![0, 1]);
+        assert_eq!(score("é", "Café").unwrap().positions, vec![3]);
+    }
+
+    fn aliases(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| (*name).to_owned()).collect()
+    }
+
+    #[test]
+    fn alias_that_matches_better_wins() {
+        let aliases = aliases(&["Thai Palace", "TP"]);
+        let (found, alias) = score_aliased("thai", "Palace of Siam", &aliases).unwrap();
+        assert_eq!(alias, Some("Thai Palace"));
+        assert_eq!(found.positions, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn name_wins_ties() {
+        let aliases = aliases(&["Heat"]);
+        let (_, alias) = score_aliased("heat", "Heat", &aliases).unwrap();
+        assert_eq!(alias, None);
+    }
+
+    #[test]
+    fn alias_alone_can_match() {
+        let aliases = aliases(&["Pad Thai"]);
+        let (_, alias) = score_aliased("pad", "Noodles", &aliases).unwrap();
+        assert_eq!(alias, Some("Pad Thai"));
+        assert!(score_aliased("xyz", "Noodles", &aliases).is_none());
+    }
+}
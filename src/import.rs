@@ -0,0 +1,459 @@
+//! Checks that imported JSON has the shape of a saved board before anything is
+//! replaced, so a hand-edited or third-party file is told exactly what is wrong
+//! with it (e.g. "groups.Dinner[2] is not a list name or group") rather than
+//! getting a bare serde error.
+//!
+//! Fields the app doesn't know about are ignored, as they are when loading.
+
+use serde_json::{Map, Value};
+
+type Check = fn(&mut Checker, &Value, &str);
+
+// Every problem found, in document order
+pub fn problems(value: &Value) -> Vec<String> {
+    let mut checker = Checker::default();
+    checker.state(value);
+    checker.problems
+}
+
+#[derive(Default)]
+struct Checker {
+    problems: Vec<String>,
+}
+
+impl Checker {
+    fn state(&mut self, value: &Value) {
+        let root = match value.as_object() {
+            Some(root) => root,
+            None => {
+                self.problems
+                    .push("the file is not a JSON object".to_owned());
+                return;
+            }
+        };
+        self.required(root, "", &["lists", "groups"]);
+        self.fields(
+            root,
+            "",
+            &[
+                ("lists", |c, v, p| {
+                    c.map(v, p, |c, v, p| c.array(v, p, Checker::item))
+                }),
+                ("groups", |c, v, p| {
+                    c.map(v, p, |c, v, p| c.array(v, p, Checker::group_entry))
+                }),
+                ("list_icons", |c, v, p| c.map(v, p, Checker::string)),
+                ("list_settings", |c, v, p| {
+                    c.map(v, p, Checker::list_settings)
+                }),
+                ("list_max", |c, v, p| c.map(v, p, Checker::count)),
+                ("group_backgrounds", |c, v, p| c.map(v, p, Checker::string)),
+                ("current_list", Checker::optional_string),
+                ("current_group", Checker::optional_string),
+                ("trashed", |c, v, p| c.array(v, p, Checker::trashed)),
+                ("fetch_link_details", Checker::boolean),
+                ("list_order", |c, v, p| c.array(v, p, Checker::string)),
+                ("group_order", |c, v, p| c.array(v, p, Checker::string)),
+                ("history", |c, v, p| c.array(v, p, Checker::draw)),
+                ("fair_draws", Checker::boolean),
+                ("autofreeze", Checker::boolean),
+                ("history_ttl_days", |c, v, p| {
+                    c.integer(v, p, u32::MAX as u64)
+                }),
+                ("item_editor_dialog", Checker::boolean),
+                ("group_settings", |c, v, p| {
+                    c.map(v, p, Checker::group_settings)
+                }),
+                ("spin", Checker::spin),
+                ("list_modified", |c, v, p| c.map(v, p, Checker::number)),
+            ],
+        );
+    }
+    fn item(&mut self, value: &Value, path: &str) {
+        if let Some(item) = self.object(value, path) {
+            self.fields(
+                item,
+                path,
+                &[
+                    ("name", Checker::optional_string),
+                    ("image", Checker::optional_string),
+                    ("link", Checker::optional_string),
+                    ("comment", Checker::optional_string),
+                    ("weight", Checker::optional_weight),
+                    ("enabled", Checker::boolean),
+                    ("last_chosen", Checker::optional_number),
+                    ("tags", |c, v, p| c.array(v, p, Checker::string)),
+                    ("pinned", Checker::boolean),
+                    ("cooldown", Checker::count),
+                    ("tier", Checker::optional_string),
+                    ("hide_in_flash", Checker::boolean),
+                    ("css_class", Checker::optional_string),
+                    ("position_locked", Checker::boolean),
+                    ("aliases", |c, v, p| c.array(v, p, Checker::string)),
+                    ("value", Checker::optional_number),
+                    ("weekdays", |c, v, p| {
+                        if !v.is_null() {
+                            c.integer(v, p, 0b111_1111)
+                        }
+                    }),
+                ],
+            );
+        }
+    }
+    fn group_entry(&mut self, value: &Value, path: &str) {
+        let valid = match value {
+            Value::String(_) => true,
+            Value::Object(entry) => entry.get("group").map(Value::is_string).unwrap_or(false),
+            _ => false,
+        };
+        if !valid {
+            self.problem(path, "is not a list name or group");
+        }
+    }
+    fn list_settings(&mut self, value: &Value, path: &str) {
+        if let Some(settings) = self.object(value, path) {
+            self.fields(
+                settings,
+                path,
+                &[
+                    ("consume_on_roll", Checker::boolean),
+                    ("rotation", Checker::boolean),
+                    ("percentages", Checker::boolean),
+                    ("draw_mode", |c, v, p| {
+                        c.one_of(v, p, &["weighted", "uniform", "bag"])
+                    }),
+                    ("cooldown", Checker::count),
+                    ("tiers", |c, v, p| c.array(v, p, Checker::tier)),
+                    ("tickets", Checker::boolean),
+                    ("template", Checker::optional_string),
+                    ("required", |c, v, p| {
+                        if !v.is_null() {
+                            c.one_of(v, p, &["image", "link"])
+                        }
+                    }),
+                    ("warn_missing", Checker::boolean),
+                    ("on_empty", |c, v, p| {
+                        c.one_of(v, p, &["placeholder", "remove_from_groups", "keep"])
+                    }),
+                    ("draw_style", |c, v, p| {
+                        c.one_of(v, p, &["flash", "fade", "flip", "scroll"])
+                    }),
+                    ("bag", |c, v, p| c.array(v, p, Checker::count)),
+                    ("numeric", |c, v, p| {
+                        if !v.is_null() {
+                            c.numeric(v, p)
+                        }
+                    }),
+                ],
+            );
+        }
+    }
+    fn tier(&mut self, value: &Value, path: &str) {
+        if let Some(tier) = self.object(value, path) {
+            self.required(tier, path, &["name", "weight"]);
+            self.fields(
+                tier,
+                path,
+                &[("name", Checker::string), ("weight", Checker::weight)],
+            );
+        }
+    }
+    fn numeric(&mut self, value: &Value, path: &str) {
+        if let Some(numeric) = self.object(value, path) {
+            self.required(numeric, path, &["min", "max", "step"]);
+            self.fields(
+                numeric,
+                path,
+                &[
+                    ("min", Checker::whole),
+                    ("max", Checker::whole),
+                    ("step", Checker::whole),
+                ],
+            );
+        }
+    }
+    fn group_settings(&mut self, value: &Value, path: &str) {
+        if let Some(settings) = self.object(value, path) {
+            self.fields(
+                settings,
+                path,
+                &[
+                    ("reveal_order", |c, v, p| {
+                        c.one_of(v, p, &["as_listed", "reversed", "shuffled"])
+                    }),
+                    ("celebrate", Checker::boolean),
+                    ("countdown_seconds", |c, v, p| {
+                        if !v.is_null() {
+                            c.integer(v, p, u32::MAX as u64)
+                        }
+                    }),
+                    ("preview_links", Checker::boolean),
+                    ("exclusive_reveal", Checker::boolean),
+                    ("value_cap", Checker::optional_number),
+                    ("weights", |c, v, p| {
+                        c.map(v, p, |c, v, p| c.map(v, p, Checker::weight))
+                    }),
+                ],
+            );
+        }
+    }
+    fn spin(&mut self, value: &Value, path: &str) {
+        if let Some(spin) = self.object(value, path) {
+            self.fields(
+                spin,
+                path,
+                &[
+                    ("duration_ms", Checker::number),
+                    ("easing", |c, v, p| {
+                        c.one_of(v, p, &["linear", "ease_out", "bounce"])
+                    }),
+                ],
+            );
+        }
+    }
+    fn trashed(&mut self, value: &Value, path: &str) {
+        match value.as_array().map(Vec::as_slice) {
+            Some([list, item]) => {
+                self.string(list, &format!("{}[0]", path));
+                self.item(item, &format!("{}[1]", path));
+            }
+            _ => self.problem(path, "is not a [list, item] pair"),
+        }
+    }
+    fn draw(&mut self, value: &Value, path: &str) {
+        if let Some(draw) = self.object(value, path) {
+            self.required(draw, path, &["time", "list"]);
+            self.fields(
+                draw,
+                path,
+                &[
+                    ("time", Checker::number),
+                    ("list", Checker::string),
+                    ("item", Checker::optional_string),
+                    ("proof", |c, v, p| {
+                        if !v.is_null() {
+                            c.proof(v, p)
+                        }
+                    }),
+                ],
+            );
+        }
+    }
+    fn proof(&mut self, value: &Value, path: &str) {
+        if let Some(proof) = self.object(value, path) {
+            self.required(proof, path, &["seed", "algorithm", "candidates", "winner"]);
+            self.fields(
+                proof,
+                path,
+                &[
+                    ("seed", |c, v, p| c.integer(v, p, u64::MAX)),
+                    ("algorithm", Checker::string),
+                    ("attempts", Checker::count),
+                    ("candidates", |c, v, p| {
+                        c.array(v, p, |c, v, p| match v.as_array().map(Vec::as_slice) {
+                            Some([name, weight]) => {
+                                c.optional_string(name, &format!("{}[0]", p));
+                                c.number(weight, &format!("{}[1]", p));
+                            }
+                            _ => c.problem(p, "is not a [name, weight] pair"),
+                        })
+                    }),
+                    ("winner", Checker::count),
+                ],
+            );
+        }
+    }
+
+    fn problem(&mut self, path: &str, reason: &str) {
+        self.problems.push(format!("{} {}", path, reason));
+    }
+    fn required(&mut self, object: &Map<String, Value>, path: &str, names: &[&str]) {
+        for name in names {
+            if !object.contains_key(*name) {
+                self.problem(&join(path, name), "is missing");
+            }
+        }
+    }
+    // Check whichever of the known fields are present
+    fn fields(&mut self, object: &Map<String, Value>, path: &str, checks: &[(&str, Check)]) {
+        for (name, check) in checks {
+            if let Some(value) = object.get(*name) {
+                check(self, value, &join(path, name));
+            }
+        }
+    }
+    fn object<'a>(&mut self, value: &'a Value, path: &str) -> Option<&'a Map<String, Value>> {
+        let object = value.as_object();
+        if object.is_none() {
+            self.problem(path, "is not an object");
+        }
+        object
+    }
+    fn map(&mut self, value: &Value, path: &str, check: Check) {
+        if let Some(object) = self.object(value, path) {
+            for (key, value) in object {
+                check(self, value, &join(path, key));
+            }
+        }
+    }
+    fn array(&mut self, value: &Value, path: &str, check: Check) {
+        match value.as_array() {
+            Some(array) => {
+                for (idx, value) in array.iter().enumerate() {
+                    check(self, value, &format!("{}[{}]", path, idx));
+                }
+            }
+            None => self.problem(path, "is not a list"),
+        }
+    }
+    fn string(&mut self, value: &Value, path: &str) {
+        if !value.is_string() {
+            self.problem(path, "is not a string");
+        }
+    }
+    fn one_of(&mut self, value: &Value, path: &str, choices: &[&str]) {
+        match value.as_str() {
+            Some(choice) if choices.contains(&choice) => {}
+            _ => self.problem(path, &format!("is not one of {}", choices.join(", "))),
+        }
+    }
+    fn optional_string(&mut self, value: &Value, path: &str) {
+        if !value.is_null() {
+            self.string(value, path);
+        }
+    }
+    fn boolean(&mut self, value: &Value, path: &str) {
+        if !value.is_boolean() {
+            self.problem(path, "is not true or false");
+        }
+    }
+    fn number(&mut self, value: &Value, path: &str) {
+        if !value.is_number() {
+            self.problem(path, "is not a number");
+        }
+    }
+    // A weight below 0 would take odds away from the rest of its list
+    fn weight(&mut self, value: &Value, path: &str) {
+        match value.as_f64() {
+            Some(weight) if weight < 0.0 => self.problem(path, "is below 0"),
+            Some(_) => {}
+            None => self.problem(path, "is not a number"),
+        }
+    }
+    fn optional_weight(&mut self, value: &Value, path: &str) {
+        if !value.is_null() {
+            self.weight(value, path);
+        }
+    }
+    fn optional_number(&mut self, value: &Value, path: &str) {
+        if !value.is_null() {
+            self.number(value, path);
+        }
+    }
+    // Any integer that fits in an i64, negative or not
+    fn whole(&mut self, value: &Value, path: &str) {
+        if value.as_i64().is_none() {
+            self.problem(path, "is not a whole number");
+        }
+    }
+    fn count(&mut self, value: &Value, path: &str) {
+        self.integer(value, path, usize::MAX as u64);
+    }
+    fn integer(&mut self, value: &Value, path: &str, max: u64) {
+        match value.as_u64() {
+            Some(n) if n <= max => {}
+            _ => self.problem(path, &format!("is not a whole number from 0 to {}", max)),
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{}.{}", path, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_a_saved_board() {
+        let board = json!({
+            "lists": {
+                "Movies": [
+                    {"name": "Heat", "image": null, "link": null, "comment": null},
+                    {"name": "Arrival", "weight": 2.5, "enabled": false, "weekdays": 65},
+                ],
+            },
+            "groups": {"Night": ["Movies", {"group": "Snacks"}]},
+            "list_settings": {"Movies": {"consume_on_roll": true}},
+            "list_max": {"Movies": 10},
+            "trashed": [["Movies", {"name": "Alien"}]],
+            "history": [{"time": 1.0, "list": "Movies", "item": "Heat"}],
+            "someday": "fields the app doesn't know are ignored",
+        });
+        assert!(problems(&board).is_empty(), "{:?}", problems(&board));
+    }
+
+    #[test]
+    fn names_each_problem_by_path() {
+        let board = json!({
+            "lists": {"Movies": [{"weight": "heavy"}, 3, {"weight": -5}]},
+            "groups": {"Dinner": ["Movies", 7]},
+            "list_settings": {
+                "Movies": {"rotation": "yes", "tiers": [{"name": "Rare", "weight": -1}]},
+            },
+            "list_max": {"Movies": -1},
+            "trashed": [["Movies"]],
+        });
+        assert_eq!(
+            problems(&board),
+            vec![
+                "lists.Movies[0].weight is not a number",
+                "lists.Movies[1] is not an object",
+                "lists.Movies[2].weight is below 0",
+                "groups.Dinner[1] is not a list name or group",
+                "list_settings.Movies.rotation is not true or false",
+                "list_settings.Movies.tiers[0].weight is below 0",
+                &format!(
+                    "list_max.Movies is not a whole number from 0 to {}",
+                    usize::MAX
+                ),
+                "trashed[0] is not a [list, item] pair",
+            ]
+        );
+    }
+
+    #[test]
+    fn requires_lists_and_groups() {
+        assert_eq!(
+            problems(&json!({})),
+            vec!["lists is missing", "groups is missing"]
+        );
+        assert_eq!(problems(&json!([])), vec!["the file is not a JSON object"]);
+    }
+
+    #[test]
+    fn checks_draw_proofs() {
+        let board = json!({
+            "lists": {},
+            "groups": {},
+            "history": [{
+                "time": 1.0,
+                "list": "Movies",
+                "proof": {"seed": 7, "algorithm": "weighted", "candidates": [["Heat", 1], [null]]},
+            }],
+        });
+        assert_eq!(
+            problems(&board),
+            vec![
+                "history[0].proof.winner is missing",
+                "history[0].proof.candidates[1] is not a [name, weight] pair",
+            ]
+        );
+    }
+}
@@ -1,6 +1,12 @@
 #![recursion_limit = "512"]
 
 mod app;
+mod bookmarks;
+mod fuzzy;
+mod import;
+mod outline;
+mod page;
+mod template;
 mod utils;
 
 use wasm_bindgen::prelude::*;
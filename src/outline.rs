@@ -0,0 +1,157 @@
+//! Parser for an indented outline of groups, lists, and items:
+//!
+//! ```text
+//! Date Night
+//!     Movies
+//!         Arrival
+//!         Heat
+//!     Restaurants
+//!         Thai Palace
+//! ```
+//!
+//! Unindented lines are groups, the next indentation level holds the lists in
+//! that group, and anything indented below a list is an item name.  A list that
+//! appears under more than one group collects the items from every occurrence.
+
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Default)]
+pub struct Outline {
+    pub lists: BTreeMap<String, Vec<String>>,
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+pub struct OutlineError {
+    pub line: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for OutlineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.reason)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Outline, OutlineError> {
+    let mut outline = Outline::default();
+    // Indentation widths of the list and item levels, fixed by the first
+    // line seen at each level.
+    let mut indents: [Option<usize>; 2] = [None, None];
+    let mut group: Option<String> = None;
+    let mut list: Option<String> = None;
+    for (idx, raw) in text.lines().enumerate() {
+        let line = idx + 1;
+        let entry = raw.trim();
+        if entry.is_empty() {
+            continue;
+        }
+        let indent = raw.len() - raw.trim_start().len();
+        let depth = match (indent, indents) {
+            (0, _) => 0,
+            (n, [None, _]) => {
+                indents[0] = Some(n);
+                1
+            }
+            (n, [Some(lists), _]) if n == lists => 1,
+            (n, [Some(lists), None]) if n > lists => {
+                indents[1] = Some(n);
+                2
+            }
+            (n, [Some(_), Some(items)]) if n == items => 2,
+            _ => {
+                return Err(OutlineError {
+                    line,
+                    reason: "indentation doesn't match any earlier line",
+                })
+            }
+        };
+        match depth {
+            0 => {
+                outline.groups.entry(entry.to_owned()).or_default();
+                group = Some(entry.to_owned());
+                list = None;
+            }
+            1 => {
+                let group = group.as_ref().ok_or(OutlineError {
+                    line,
+                    reason: "list appears before any group",
+                })?;
+                outline.lists.entry(entry.to_owned()).or_default();
+                let members = outline.groups.get_mut(group).unwrap();
+                if !members.iter().any(|x| x == entry) {
+                    members.push(entry.to_owned());
+                }
+                list = Some(entry.to_owned());
+            }
+            _ => {
+                let list = list.as_ref().ok_or(OutlineError {
+                    line,
+                    reason: "item appears before any list",
+                })?;
+                outline.lists.get_mut(list).unwrap().push(entry.to_owned());
+            }
+        }
+    }
+    if outline.groups.is_empty() {
+        return Err(OutlineError {
+            line: 1,
+            reason: "outline is empty",
+        });
+    }
+    Ok(outline)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(text: &str) -> String {
+        match parse(text) {
+            Ok(_) => panic!("parsed {:?}", text),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn groups_lists_and_items() {
+        let outline = parse(
+            "Date Night\n    Movies\n        Arrival\n        Heat\n    Restaurants\n        Thai Palace\n",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(outline.groups["Date Night"], vec!["Movies", "Restaurants"]);
+        assert_eq!(outline.lists["Movies"], vec!["Arrival", "Heat"]);
+        assert_eq!(outline.lists["Restaurants"], vec!["Thai Palace"]);
+    }
+
+    #[test]
+    fn shared_list_collects_every_occurrence() {
+        let outline = parse("A\n\tMovies\n\t\tHeat\n\nB\n\tMovies\n\t\tArrival\n")
+            .ok()
+            .unwrap();
+        assert_eq!(outline.groups["A"], vec!["Movies"]);
+        assert_eq!(outline.groups["B"], vec!["Movies"]);
+        assert_eq!(outline.lists["Movies"], vec!["Heat", "Arrival"]);
+    }
+
+    #[test]
+    fn empty_group_and_list() {
+        let outline = parse("Empty\nA\n  Nothing yet\n").ok().unwrap();
+        assert!(outline.groups["Empty"].is_empty());
+        assert!(outline.lists["Nothing yet"].is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_outlines() {
+        assert_eq!(error(""), "Line 1: outline is empty");
+        assert_eq!(error("  Movies\n"), "Line 1: list appears before any group");
+        assert_eq!(
+            error("A\n    Movies\n  Heat\n"),
+            "Line 3: indentation doesn't match any earlier line"
+        );
+        assert_eq!(
+            error("A\n  Movies\n    Heat\n      Deeper\n"),
+            "Line 4: indentation doesn't match any earlier line"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Just enough HTML scraping to pull a page's title and icon out of a fetched
+//! document.  This is a plain text search, not a parser, so it only copes with
+//! reasonably ordinary markup.
+
+pub fn title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let open = lower.find("<title")?;
+    let start = open + lower[open..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = decode_entities(html[start..end].trim());
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+// The href of the first <link rel="icon"> (or "shortcut icon"), as written
+pub fn icon_href(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find("<link") {
+        let start = rest + found;
+        let end = start + lower[start..].find('>')?;
+        let tag = &html[start..end];
+        let rel = attribute(tag, "rel")
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if rel.split_whitespace().any(|word| word == "icon") {
+            return attribute(tag, "href").map(|href| decode_entities(&href));
+        }
+        rest = end;
+    }
+    None
+}
+
+pub fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut rest = 0;
+    while let Some(found) = lower[rest..].find(name) {
+        let start = rest + found;
+        rest = start + name.len();
+        // Only whole attribute names, e.g. not the "rel" in "data-rel"
+        let before = lower[..start].chars().last();
+        if before.map(|c| !c.is_whitespace()).unwrap_or(true) {
+            continue;
+        }
+        let value = lower[rest..].trim_start();
+        if !value.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - value[1..].trim_start().len();
+        let value = &tag[value_start..];
+        return match value.chars().next()? {
+            quote @ '"' | quote @ '\'' => value[1..].split(quote).next().map(str::to_owned),
+            _ => value.split_whitespace().next().map(str::to_owned),
+        };
+    }
+    None
+}
+
+pub fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
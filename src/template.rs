@@ -0,0 +1,100 @@
+//! Per-list display templates for a drawn item, e.g. "{name} ({comment})".
+//!
+//! Only the placeholders in `PLACEHOLDERS` are filled in.  Anything else,
+//! including an unknown `{word}`, stays as plain text, and an item's own text
+//! is never treated as markup.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Field {
+    Name,
+    Image,
+    Link,
+    Comment,
+}
+
+pub enum Part<'a> {
+    Text(&'a str),
+    Field(Field),
+}
+
+pub const PLACEHOLDERS: [(&str, Field); 4] = [
+    ("name", Field::Name),
+    ("image", Field::Image),
+    ("link", Field::Link),
+    ("comment", Field::Comment),
+];
+
+pub fn parse(template: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let field = rest[open..].find('}').and_then(|close| {
+            let word = &rest[open + 1..open + close];
+            PLACEHOLDERS
+                .iter()
+                .find(|(placeholder, _)| *placeholder == word)
+                .map(|(_, field)| (*field, open + close + 1))
+        });
+        match field {
+            Some((field, end)) => {
+                if open > 0 {
+                    parts.push(Part::Text(&rest[..open]));
+                }
+                parts.push(Part::Field(field));
+                rest = &rest[end..];
+            }
+            // Not a placeholder, so the brace is just text
+            None => {
+                parts.push(Part::Text(&rest[..=open]));
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    if !rest.is_empty() {
+        parts.push(Part::Text(rest));
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fields as "[name]" and text as is, to compare parses as strings
+    fn render(template: &str) -> String {
+        parse(template)
+            .into_iter()
+            .map(|part| match part {
+                Part::Text(text) => text.to_owned(),
+                Part::Field(field) => {
+                    let (placeholder, _) = PLACEHOLDERS
+                        .iter()
+                        .find(|(_, known)| *known == field)
+                        .unwrap();
+                    format!("[{}]", placeholder)
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fills_known_placeholders() {
+        assert_eq!(render("{name} ({comment})"), "[name] ([comment])");
+        assert_eq!(render("{image}{link}"), "[image][link]");
+    }
+
+    #[test]
+    fn leaves_other_braces_as_text() {
+        assert_eq!(render("{price} {name}"), "{price} [name]");
+        assert_eq!(render("{{name}}"), "{[name]}");
+        assert_eq!(render("{name"), "{name");
+        assert_eq!(render("}{"), "}{");
+    }
+
+    #[test]
+    fn keeps_text_in_one_piece() {
+        assert_eq!(parse("").len(), 0);
+        assert_eq!(parse("plain").len(), 1);
+        assert_eq!(parse("a {name} b").len(), 3);
+    }
+}
@@ -8,3 +8,72 @@ pub fn set_panic_hook() {
     #[cfg(feature = "console_error_panic_hook")]
     console_error_panic_hook::set_once();
 }
+
+// Look up a parameter in the page's query string, e.g. `?mirror=1`.
+pub fn query_param(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(name)
+}
+
+// Take a parameter out of the address bar without reloading or adding a
+// history entry.
+pub fn remove_query_param(name: &str) -> Option<()> {
+    let window = web_sys::window()?;
+    let location = window.location();
+    let params = web_sys::UrlSearchParams::new_with_str(&location.search().ok()?).ok()?;
+    params.delete(name);
+    let search = String::from(params.to_string());
+    let url = format!(
+        "{}{}{}{}",
+        location.pathname().ok()?,
+        if search.is_empty() { "" } else { "?" },
+        search,
+        location.hash().ok()?
+    );
+    window
+        .history()
+        .ok()?
+        .replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url))
+        .ok()
+}
+
+// Base64 with the URL-safe alphabet and no padding, so it can sit in a query
+// string as is.  btoa() wants a "binary string", one char per byte.
+pub fn base64url_encode(bytes: &[u8]) -> Option<String> {
+    let binary: String = bytes.iter().map(|byte| *byte as char).collect();
+    let base64 = web_sys::window()?.btoa(&binary).ok()?;
+    Some(
+        base64
+            .trim_end_matches('=')
+            .chars()
+            .map(|c| match c {
+                '+' => '-',
+                '/' => '_',
+                c => c,
+            })
+            .collect(),
+    )
+}
+
+pub fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let base64: String = text
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    let binary = web_sys::window()?.atob(&base64).ok()?;
+    binary
+        .chars()
+        .map(|c| std::convert::TryFrom::try_from(c as u32).ok())
+        .collect()
+}
+
+// yew's DialogService has no prompt(), so go straight to the window.
+pub fn prompt(message: &str) -> Option<String> {
+    web_sys::window()?.prompt_with_message(message).ok()?
+}
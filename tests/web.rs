@@ -3,7 +3,9 @@
 #![cfg(target_arch = "wasm32")]
 
 extern crate wasm_bindgen_test;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_test::*;
+use web_sys::{HtmlElement, HtmlTextAreaElement, InputEvent};
 
 wasm_bindgen_test_configure!(run_in_browser);
 
@@ -11,3 +13,42 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+// Each keystroke re-renders the edit form, which mustn't send the caret to
+// the end of the comment.
+#[wasm_bindgen_test]
+fn typing_mid_comment_keeps_caret() {
+    let window = web_sys::window().unwrap();
+    let storage = window.local_storage().unwrap().unwrap();
+    storage.remove_item("automatic-spoon.boards").unwrap();
+    storage
+        .set_item(
+            "automatic-spoon.self",
+            r#"{"lists":{"L":[{"name":"x","image":null,"link":null,"comment":"hello world"}]},"groups":{},"current_list":"L"}"#,
+        )
+        .unwrap();
+    yew_wasm_pack_template::run_app().unwrap();
+    let document = window.document().unwrap();
+    document
+        .query_selector(".entries li")
+        .unwrap()
+        .unwrap()
+        .dyn_into::<HtmlElement>()
+        .unwrap()
+        .click();
+    let comment = document
+        .get_element_by_id("item-comment")
+        .unwrap()
+        .dyn_into::<HtmlTextAreaElement>()
+        .unwrap();
+    assert_eq!(comment.value(), "hello world");
+
+    comment.set_value("hello, world");
+    comment.set_selection_range(6, 6).unwrap();
+    comment
+        .dispatch_event(&InputEvent::new("input").unwrap())
+        .unwrap();
+
+    assert_eq!(comment.value(), "hello, world");
+    assert_eq!(comment.selection_start().unwrap(), Some(6));
+}